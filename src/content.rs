@@ -115,7 +115,7 @@ fn validate(text: &str) -> crate::Result<()> {
         return Err(Error::EmptyContent);
     }
 
-    if text.contains(&['\n', '\r']) {
+    if text.contains(['\n', '\r']) {
         return Err(Error::InvalidContent);
     }
 
@@ -1,13 +1,45 @@
-use crate::{Block, Content, Level, Link, Preformatted, Result};
-use http::uri::Uri;
+use crate::{
+    gemtext, Block, BlockKind, Content, Error, Field, Level, Link, Preformatted, Report, Result,
+    UriRef,
+};
 
 /// A document builder
 ///
+/// Setters check their values as they are appended.
+/// An invalid value is reported by [`build`] along with the index of its block
+/// and the name of the setter, or right away by the `try_` variant of the setter.
+///
 /// # Example
 ///
 /// See [crate documentation](./index.html#examples).
+///
+/// Find the invalid value
+/// ```
+/// use mdiu::{Document, Error};
+///
+/// let error = Document::new()
+///     .h1("my gemlog")
+///     .text("two\nlines")
+///     .build()
+///     .unwrap_err();
+/// assert_eq!(
+///     error.to_string(),
+///     "text of block 1 (text line) from text at byte 3: \
+///      invalid Content: contains newline characters: \"two\\nlines\""
+/// );
+///
+/// let result = Document::new().try_h1("my gemlog").and_then(|doc| doc.try_text(""));
+/// assert!(matches!(result, Err(Error::Block { index: 1, .. })));
+/// ```
+///
+/// [`build`]: Document::build
 #[derive(Debug, Default, Clone)]
-pub struct Document(Vec<Block>);
+pub struct Document {
+    blocks: Vec<Block>,
+    strict: bool,
+    /// Errors from setters, whose blocks were not appended
+    errors: Vec<Error>,
+}
 
 macro_rules! setter {
     (
@@ -16,20 +48,48 @@ macro_rules! setter {
     ) => {
         $(#[$attr])*
         pub fn $func(mut self, $($param: $ty),*) -> Self {
-            self.0.push($block);
+            self.blocks.push($block);
             self
         }
     };
     (
         $(#[$attr:meta])*
-        pub fn $func:ident<T>($($param:ident: $ty:ty),*) $block:block
+        pub fn $func:ident / $try_func:ident<T>($($param:ident: $ty:ty),*) $block:block
     ) => {
         $(#[$attr])*
+        ///
+        /// An invalid value is not appended, and is returned as an error by [`validate`](Self::validate).
         pub fn $func<T>(mut self, $($param: $ty),*) -> Self
         where T: Into<String> {
-            self.0.push($block);
+            let block: std::result::Result<Block, Invalid> = $block;
+            match block {
+                Ok(block) => self.blocks.push(block),
+                Err(error) => {
+                    let error = self.builder_error(stringify!($func), error);
+                    self.errors.push(error);
+                }
+            }
             self
         }
+
+        #[doc = concat!(
+            "Like [`", stringify!($func), "`](Self::", stringify!($func), "), ",
+            "but returns an invalid value as an error right away"
+        )]
+        ///
+        /// # Errors
+        /// Returns an [`Error::Block`] with the index of the block and the name of the method.
+        pub fn $try_func<T>(mut self, $($param: $ty),*) -> Result<Self>
+        where T: Into<String> {
+            let block: std::result::Result<Block, Invalid> = $block;
+            match block {
+                Ok(block) => {
+                    self.blocks.push(block);
+                    Ok(self)
+                }
+                Err(error) => Err(self.builder_error(stringify!($func), error)),
+            }
+        }
     };
 }
 
@@ -39,79 +99,100 @@ impl Document {
         Self::default()
     }
 
+    /// Enables strict validation
+    ///
+    /// Text that a Gemtext client would read as another line type,
+    /// such as `# not a heading` or `=> not a link`, is rejected by [`validate`].
+    ///
+    /// # Example
+    /// ```
+    /// # use mdiu::*;
+    /// let doc = Document::new().text("# not a heading");
+    /// assert_eq!(Ok(()), doc.validate());
+    ///
+    /// let doc = doc.strict();
+    /// assert!(matches!(
+    ///     doc.validate(),
+    ///     Err(Error::Block { error, .. }) if *error == Error::AmbiguousText
+    /// ));
+    /// ```
+    ///
+    /// [`validate`]: #method.validate
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
     setter! {
         /// Appends text
-        pub fn text<T>(text: T) {
-            let content = unsafe { Content::new_unchecked(text) };
-            Block::Text(content)
+        pub fn text / try_text<T>(text: T) {
+            content(BlockKind::Text, Field::Text, text).map(Block::Text)
         }
     }
 
     setter! {
-        /// Appends a link
-        pub fn link(uri: Uri) {
+        /// Appends a link, to an [`http::Uri`] or any [`UriRef`]
+        pub fn link(uri: impl Into<UriRef>) {
             Block::Link(Link::new(uri, None))
         }
     }
     setter! {
         /// Appends a link with label
-        pub fn link_with_label<T>(uri: Uri, label: T) {
-            let label = unsafe { Content::new_unchecked(label) };
-            Block::Link(Link::new(uri, Some(label)))
+        pub fn link_with_label / try_link_with_label<T>(uri: impl Into<UriRef>, label: T) {
+            content(BlockKind::Link, Field::Label, label)
+                .map(|label| Block::Link(Link::new(uri, Some(label))))
         }
     }
 
     setter! {
         /// Appends a heading
-        pub fn h1<T>(text: T) {
-            let content = unsafe { Content::new_unchecked(text) };
-            Block::Heading(Level::One, content)
+        pub fn h1 / try_h1<T>(text: T) {
+            content(BlockKind::Heading, Field::Text, text)
+                .map(|content| Block::Heading(Level::One, content))
         }
     }
 
     setter! {
         /// Appends a subheading
-        pub fn h2<T>(text: T) {
-            let content = unsafe { Content::new_unchecked(text) };
-            Block::Heading(Level::Two, content)
+        pub fn h2 / try_h2<T>(text: T) {
+            content(BlockKind::Heading, Field::Text, text)
+                .map(|content| Block::Heading(Level::Two, content))
         }
     }
 
     setter! {
         /// Appends a sub-subheading
-        pub fn h3<T>(text: T) {
-            let content = unsafe { Content::new_unchecked(text) };
-            Block::Heading(Level::Three, content)
+        pub fn h3 / try_h3<T>(text: T) {
+            content(BlockKind::Heading, Field::Text, text)
+                .map(|content| Block::Heading(Level::Three, content))
         }
     }
 
     setter! {
         /// Appends a list item
-        pub fn list_item<T>(text: T) {
-            let content = unsafe { Content::new_unchecked(text) };
-            Block::ListItem(content)
+        pub fn list_item / try_list_item<T>(text: T) {
+            content(BlockKind::ListItem, Field::Text, text).map(Block::ListItem)
         }
     }
 
     setter! {
         /// Appends a blockquote
-        pub fn quote<T>(text: T) {
-            let content = unsafe { Content::new_unchecked(text) };
-            Block::Quote(content)
+        pub fn quote / try_quote<T>(text: T) {
+            content(BlockKind::Quote, Field::Text, text).map(Block::Quote)
         }
     }
 
     setter! {
         /// Appends preformatted text
-        pub fn preformatted<T>(text: T) {
-            Block::Preformatted(Preformatted::new(text, None))
+        pub fn preformatted / try_preformatted<T>(text: T) {
+            preformatted(text.into(), None)
         }
     }
     setter! {
         /// Appends preformatted text with alt text
-        pub fn preformatted_with_alt<T>(text: T, alt: T) {
-            let alt = unsafe { Content::new_unchecked(alt) };
-            Block::Preformatted(Preformatted::new(text, Some(alt)))
+        pub fn preformatted_with_alt / try_preformatted_with_alt<T>(text: T, alt: T) {
+            content(BlockKind::Preformatted, Field::Alt, alt)
+                .and_then(|alt| preformatted(text.into(), Some(alt)))
         }
     }
 
@@ -122,21 +203,139 @@ impl Document {
         }
     }
 
+    /// Describes a value rejected by a setter, with the index its block would have had
+    fn builder_error(&self, method: &'static str, invalid: Invalid) -> Error {
+        let index = self.blocks.len() + self.errors.len();
+        let Invalid {
+            kind,
+            field,
+            value,
+            error,
+        } = invalid;
+        Error::block(index, kind, field, Some(method), &value, error)
+    }
+
+    /// Returns the field, value and error of an invalid block
+    fn invalid_field<'a>(&self, block: &'a Block) -> Option<(Field, &'a str, Error)> {
+        let check = |field, content: &'a Content| {
+            content
+                .validate()
+                .err()
+                .map(|error| (field, content.as_ref(), error))
+        };
+        match block {
+            Block::Text(content) => check(Field::Text, content).or_else(|| {
+                let error = gemtext::validate_text(content.as_ref()).err()?;
+                self.strict
+                    .then_some((Field::Text, content.as_ref(), error))
+            }),
+            Block::Link(link) => link.label().as_ref().and_then(|c| check(Field::Label, c)),
+            Block::Heading(_, content) | Block::ListItem(content) | Block::Quote(content) => {
+                check(Field::Text, content)
+            }
+            Block::Preformatted(pre) => match pre.validate() {
+                Ok(()) => None,
+                Err(Error::InvalidPreformatted) => {
+                    Some((Field::Text, pre.text(), Error::InvalidPreformatted))
+                }
+                Err(error) => Some((
+                    Field::Alt,
+                    pre.alt().as_ref().map_or("", |alt| alt.as_ref()),
+                    error,
+                )),
+            },
+            Block::Empty => None,
+        }
+    }
+
     /// Validates the [`Content`] in a `Document`
     ///
     /// # Errors
-    /// If any [`Content`] is empty or contains newline characters, an [`Error`] will be returned.
+    /// Returns an [`Error::Block`] describing the first invalid value, which may be
+    /// a value rejected by a setter, or a block that was appended invalid:
     ///
-    /// [`Error`]: crate::Error
+    /// * [`Content`] that is empty or contains newline characters
+    /// * Preformatted text that contains a line starting with ` ``` `
+    /// * In [`strict`] mode, text starting with a Gemtext line-type prefix
+    ///
+    /// Use [`validate_all`] to find every invalid value at once.
+    ///
+    /// [`strict`]: #method.strict
+    /// [`validate_all`]: #method.validate_all
     pub fn validate(&self) -> Result<()> {
-        self.0.iter().try_for_each(|block| match block {
-            Block::Text(content) => content.validate(),
-            Block::Link(link) => link.label().as_ref().map_or(Ok(()), |c| c.validate()),
-            Block::Heading(_, content) => content.validate(),
-            Block::ListItem(content) => content.validate(),
-            Block::Quote(content) => content.validate(),
-            Block::Preformatted(pre) => pre.alt().as_ref().map_or(Ok(()), |c| c.validate()),
-            Block::Empty => Ok(()),
+        match self.invalid_values().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Validates the whole `Document`, collecting every invalid value
+    ///
+    /// # Errors
+    /// Returns a [`Report`] with an [`Error::Block`] for each invalid value, in document order,
+    /// for the same reasons as [`validate`].
+    ///
+    /// # Example
+    /// ```
+    /// use mdiu::{Document, Error};
+    ///
+    /// let report = Document::new()
+    ///     .h1("")
+    ///     .text("fine")
+    ///     .quote("two\nlines")
+    ///     .validate_all()
+    ///     .unwrap_err();
+    ///
+    /// let indices: Vec<_> = report
+    ///     .iter()
+    ///     .map(|error| match error {
+    ///         Error::Block { index, .. } => *index,
+    ///         _ => unreachable!(),
+    ///     })
+    ///     .collect();
+    /// assert_eq!(indices, [0, 2]);
+    /// ```
+    ///
+    /// [`Report`]: crate::Report
+    /// [`validate`]: #method.validate
+    pub fn validate_all(&self) -> std::result::Result<(), Report> {
+        let errors: Vec<Error> = self.invalid_values().collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Report::new(errors))
+        }
+    }
+
+    /// Returns an error for each invalid value, including those rejected by setters, in order
+    fn invalid_values(&self) -> impl Iterator<Item = Error> + '_ {
+        let mut rejected = self.errors.iter().peekable();
+        let mut blocks = self.blocks.iter();
+        let mut index = 0;
+
+        std::iter::from_fn(move || loop {
+            let at_index =
+                |error: &&Error| matches!(error, Error::Block { index: i, .. } if *i == index);
+            if let Some(error) = rejected.next_if(at_index) {
+                index += 1;
+                return Some(error.clone());
+            }
+
+            let block = match blocks.next() {
+                Some(block) => block,
+                None => return rejected.next().cloned(),
+            };
+            index += 1;
+            if let Some((field, value, error)) = self.invalid_field(block) {
+                return Some(Error::block(
+                    index - 1,
+                    block.kind(),
+                    field,
+                    None,
+                    value,
+                    error,
+                ));
+            }
         })
     }
 
@@ -166,6 +365,197 @@ impl Document {
     /// [`validate`]: #method.validate
     pub fn build(self) -> Result<Vec<Block>> {
         self.validate()?;
-        Ok(self.0)
+        Ok(self.blocks)
+    }
+}
+
+/// A value rejected by a setter
+struct Invalid {
+    kind: BlockKind,
+    field: Field,
+    value: String,
+    error: Error,
+}
+
+fn content<T: Into<String>>(
+    kind: BlockKind,
+    field: Field,
+    value: T,
+) -> std::result::Result<Content, Invalid> {
+    let value = value.into();
+    match value.parse() {
+        Ok(content) => Ok(content),
+        Err(error) => Err(Invalid {
+            kind,
+            field,
+            value,
+            error,
+        }),
+    }
+}
+
+fn preformatted(text: String, alt: Option<Content>) -> std::result::Result<Block, Invalid> {
+    let pre = Preformatted::new(text, alt);
+    match pre.validate() {
+        Ok(()) => Ok(Block::Preformatted(pre)),
+        Err(error) => Err(Invalid {
+            kind: BlockKind::Preformatted,
+            field: Field::Text,
+            value: pre.text().clone(),
+            error,
+        }),
+    }
+}
+
+impl From<Vec<Block>> for Document {
+    fn from(blocks: Vec<Block>) -> Self {
+        Document {
+            blocks,
+            ..Document::default()
+        }
+    }
+}
+
+/// Parses Gemtext into a `Document`, available with the `parsing` feature
+///
+/// # Example
+/// ```
+/// # fn main() -> mdiu::Result<()> {
+/// use mdiu::{Document, Gemtext, ToMarkup};
+///
+/// let doc: Document = "# my gemlog\n".parse()?;
+/// let gemtext = doc.text("welcome").build()?.to_markup::<Gemtext>();
+///
+/// assert_eq!(gemtext, "# my gemlog\nwelcome\n");
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "parsing")]
+impl std::str::FromStr for Document {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        crate::parse(s).map(Document::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use http::uri::Uri;
+
+    #[test]
+    fn builder_errors() {
+        let doc = Document::new()
+            .link_with_label(Uri::from_static("/"), "")
+            .preformatted("```")
+            .h2("valid");
+
+        assert_eq!(
+            doc.validate(),
+            Err(Error::Block {
+                index: 0,
+                kind: BlockKind::Link,
+                field: Field::Label,
+                method: Some("link_with_label"),
+                position: None,
+                snippet: String::new(),
+                error: Box::new(Error::EmptyContent),
+            })
+        );
+
+        let error = doc
+            .clone()
+            .try_preformatted_with_alt("x", "a\rb")
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "alt text of block 3 (preformatted block) from preformatted_with_alt at byte 1: \
+             invalid Content: contains newline characters: \"a\\rb\""
+        );
+
+        // Only valid blocks are appended
+        let doc = doc.try_quote("quote").unwrap();
+        assert_eq!(doc.blocks.len(), 2);
+    }
+
+    #[test]
+    fn block_errors() {
+        let pre = Preformatted::new(format!("{}\n```", "x".repeat(50)), None);
+        let doc = Document::from(vec![Block::Empty, Block::Preformatted(pre)]);
+
+        match doc.validate() {
+            Err(Error::Block {
+                index,
+                kind,
+                field,
+                method,
+                position,
+                snippet,
+                ..
+            }) => {
+                assert_eq!(
+                    (index, kind, field, method, position),
+                    (1, BlockKind::Preformatted, Field::Text, None, Some(51))
+                );
+                assert_eq!(snippet, "…xxxxxxxxx\\n```");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn report() {
+        let doc = Document::new()
+            .text("a")
+            .h1("")
+            .text("b\n")
+            .preformatted("```")
+            .empty()
+            .quote("\r")
+            .strict()
+            .text("# c");
+        let report = doc.validate_all().unwrap_err();
+
+        let errors: Vec<_> = report
+            .iter()
+            .map(|error| match error {
+                Error::Block { index, method, .. } => (*index, *method),
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (1, Some("h1")),
+                (2, Some("text")),
+                (3, Some("preformatted")),
+                (5, Some("quote")),
+                (6, None),
+            ]
+        );
+        assert_eq!(doc.validate(), Err(report.errors()[0].clone()));
+        assert_eq!(report.to_string().lines().count(), 6);
+        assert!(report.to_string().starts_with("5 invalid values\n"));
+
+        let report = Document::new().h1("").validate_all().unwrap_err();
+        assert!(report.to_string().starts_with("1 invalid value\n"));
+        assert_eq!(Document::new().text("a").validate_all(), Ok(()));
+    }
+
+    #[test]
+    fn strict() {
+        for text in ["#", "## x", "=>x", "* x", ">x", "```"] {
+            let doc = Document::new().text(text);
+            assert_eq!(doc.validate(), Ok(()));
+            assert!(matches!(
+                doc.strict().validate(),
+                Err(Error::Block { error, position: Some(0), .. }) if *error == Error::AmbiguousText
+            ));
+        }
+
+        for text in [" # x", "*x", "x => y"] {
+            assert_eq!(Document::new().strict().text(text).validate(), Ok(()));
+        }
     }
 }
@@ -1,11 +1,45 @@
+use crate::BlockKind;
 use std::error;
 use std::fmt;
+use std::io;
+use std::mem;
+use std::sync::Arc;
 
-/// Gemtext creation errors
-#[derive(Debug, PartialEq)]
+/// Gemtext creation and parsing errors
+///
+/// More variants may be added, such as for other kinds of invalid input.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
 pub enum Error {
     EmptyContent,
     InvalidContent,
+    InvalidUri,
+    AmbiguousText,
+    InvalidPreformatted,
+    InvalidLevel,
+    /// An error reading Gemtext, shared so that `Error` can be cloned
+    Io(Arc<io::Error>),
+    /// An error at a line and column of parsed Gemtext, counted from 1
+    Parse {
+        line: usize,
+        column: usize,
+        error: Box<Error>,
+    },
+    /// An invalid value in a block of a [`Document`](crate::Document)
+    #[non_exhaustive]
+    Block {
+        /// The index of the block, counting values rejected by setters as blocks
+        index: usize,
+        kind: BlockKind,
+        field: Field,
+        /// The `Document` setter that rejected the value, which is then not appended
+        method: Option<&'static str>,
+        /// The byte offset of the offending character in the field, if there is one
+        position: Option<usize>,
+        /// Part of the field from around the offending character, with special characters escaped
+        snippet: String,
+        error: Box<Error>,
+    },
 }
 
 impl fmt::Display for Error {
@@ -17,11 +51,251 @@ impl fmt::Display for Error {
             Error::InvalidContent => {
                 write!(f, "invalid Content: contains newline characters")
             }
+            Error::InvalidUri => {
+                write!(f, "invalid URI")
+            }
+            Error::AmbiguousText => {
+                write!(f, "invalid Text: starts with a line-type prefix")
+            }
+            Error::InvalidPreformatted => {
+                write!(f, "invalid Preformatted: contains a line starting with ```")
+            }
+            Error::InvalidLevel => {
+                write!(f, "invalid Level: headings have 1 to 3 levels")
+            }
+            Error::Io(error) => {
+                write!(f, "IO error: {}", error)
+            }
+            Error::Parse {
+                line,
+                column,
+                error,
+            } => {
+                write!(f, "line {}, column {}: {}", line, column, error)
+            }
+            Error::Block {
+                index,
+                kind,
+                field,
+                method,
+                position,
+                snippet,
+                error,
+            } => {
+                write!(f, "{} of block {} ({})", field, index, kind)?;
+                if let Some(method) = method {
+                    write!(f, " from {}", method)?;
+                }
+                if let Some(position) = position {
+                    write!(f, " at byte {}", position)?;
+                }
+                write!(f, ": {}: \"{}\"", error, snippet)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error.as_ref()),
+            Error::Parse { error, .. } | Error::Block { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// IO errors are equal if they have the same kind and message
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Error::Io(a), Error::Io(b)) => a.kind() == b.kind() && a.to_string() == b.to_string(),
+            (
+                Error::Parse {
+                    line,
+                    column,
+                    error,
+                },
+                Error::Parse {
+                    line: other_line,
+                    column: other_column,
+                    error: other_error,
+                },
+            ) => line == other_line && column == other_column && error == other_error,
+            (
+                Error::Block {
+                    index,
+                    kind,
+                    field,
+                    method,
+                    position,
+                    snippet,
+                    error,
+                },
+                Error::Block {
+                    index: other_index,
+                    kind: other_kind,
+                    field: other_field,
+                    method: other_method,
+                    position: other_position,
+                    snippet: other_snippet,
+                    error: other_error,
+                },
+            ) => {
+                index == other_index
+                    && kind == other_kind
+                    && field == other_field
+                    && method == other_method
+                    && position == other_position
+                    && snippet == other_snippet
+                    && error == other_error
+            }
+            // The remaining variants have no fields
+            _ => mem::discriminant(self) == mem::discriminant(other),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(Arc::new(error))
+    }
+}
+
+/// The field of a block that holds an invalid value, for [`Error::Block`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    /// The text of a line, or of a preformatted block
+    Text,
+    /// The label of a link
+    Label,
+    /// The alt text of a preformatted block
+    Alt,
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Field::Text => "text",
+            Field::Label => "label",
+            Field::Alt => "alt text",
+        })
+    }
+}
+
+impl Error {
+    /// Wraps an error in the value of a field with its position and a snippet
+    pub(crate) fn block(
+        index: usize,
+        kind: BlockKind,
+        field: Field,
+        method: Option<&'static str>,
+        value: &str,
+        error: Error,
+    ) -> Error {
+        let position = match error {
+            Error::InvalidContent => value.find(['\n', '\r']),
+            Error::AmbiguousText => Some(0),
+            Error::InvalidPreformatted => value
+                .match_indices("```")
+                .map(|(i, _)| i)
+                .find(|&i| i == 0 || value[..i].ends_with('\n')),
+            _ => None,
+        };
+        Error::Block {
+            index,
+            kind,
+            field,
+            method,
+            position,
+            snippet: snippet(value, position.unwrap_or(0)),
+            error: Box::new(error),
         }
     }
 }
 
-impl error::Error for Error {}
+/// Returns up to 40 characters of `value` around byte `position`, escaped
+fn snippet(value: &str, position: usize) -> String {
+    let start = value[..position]
+        .char_indices()
+        .rev()
+        .nth(9)
+        .map_or(0, |(i, _)| i);
+    let mut chars = value[start..].chars();
+    let mut snippet: String = chars
+        .by_ref()
+        .take(40)
+        .flat_map(char::escape_debug)
+        .collect();
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if chars.next().is_some() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+/// Every invalid value in a [`Document`](crate::Document), from
+/// [`Document::validate_all`](crate::Document::validate_all)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    errors: Vec<Error>,
+}
+
+impl Report {
+    pub(crate) fn new(errors: Vec<Error>) -> Self {
+        Report { errors }
+    }
+
+    /// Returns the errors, in document order
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    /// Returns an iterator over the errors
+    pub fn iter(&self) -> std::slice::Iter<'_, Error> {
+        self.errors.iter()
+    }
+
+    /// Consumes the report, returning the errors
+    pub fn into_errors(self) -> Vec<Error> {
+        self.errors
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.errors.len() {
+            1 => write!(f, "1 invalid value")?,
+            n => write!(f, "{} invalid values", n)?,
+        }
+        for error in &self.errors {
+            write!(f, "\n{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for Report {}
+
+impl IntoIterator for Report {
+    type Item = Error;
+    type IntoIter = std::vec::IntoIter<Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Report {
+    type Item = &'a Error;
+    type IntoIter = std::slice::Iter<'a, Error>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.iter()
+    }
+}
 
 /// A wrapper around [`Error`]
 pub type Result<T> = std::result::Result<T, Error>;
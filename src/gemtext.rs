@@ -1,29 +1,44 @@
 use super::{Block, Level, Markup};
+use std::fmt;
 
 /// A Gemtext formatter
+#[derive(Clone, Copy, Debug, Default)]
 pub struct Gemtext;
 
 impl Markup for Gemtext {
-    fn markup(blocks: &[Block]) -> String {
-        blocks
-            .iter()
-            .map(|block| match block {
-                Block::Text(text) => format!("{}\n", text),
-                Block::Link(link) => match link.label() {
-                    Some(label) => format!("=> {} {}\n", link.uri(), label),
-                    None => format!("=> {}\n", link.uri()),
-                },
-                Block::Heading(Level::One, text) => format!("# {}\n", text),
-                Block::Heading(Level::Two, text) => format!("## {}\n", text),
-                Block::Heading(Level::Three, text) => format!("### {}\n", text),
-                Block::ListItem(text) => format!("* {}\n", text),
-                Block::Quote(text) => format!("> {}\n", text),
-                Block::Preformatted(pre) => match pre.alt() {
-                    Some(alt) => format!("```{}\n{}\n```\n", alt, pre.text()),
-                    None => format!("```\n{}\n```\n", pre.text()),
-                },
-                Block::Empty => "\n".to_string(),
-            })
-            .collect::<String>()
+    fn fmt_markup<'a, I, W>(&self, blocks: I, w: &mut W) -> fmt::Result
+    where
+        I: IntoIterator<Item = &'a Block>,
+        W: fmt::Write + ?Sized,
+    {
+        blocks.into_iter().try_for_each(|block| match block {
+            Block::Text(text) => writeln!(w, "{}", text),
+            Block::Link(link) => match link.label() {
+                Some(label) => writeln!(w, "=> {} {}", link.uri(), label),
+                None => writeln!(w, "=> {}", link.uri()),
+            },
+            Block::Heading(Level::One, text) => writeln!(w, "# {}", text),
+            Block::Heading(Level::Two, text) => writeln!(w, "## {}", text),
+            Block::Heading(Level::Three, text) => writeln!(w, "### {}", text),
+            Block::ListItem(text) => writeln!(w, "* {}", text),
+            Block::Quote(text) => writeln!(w, "> {}", text),
+            Block::Preformatted(pre) => match pre.alt() {
+                Some(alt) => write!(w, "```{}\n{}\n```\n", alt, pre.text()),
+                None => write!(w, "```\n{}\n```\n", pre.text()),
+            },
+            Block::Empty => writeln!(w),
+        })
     }
 }
+
+/// Line-type prefixes, which cannot start a text line
+const PREFIXES: [&str; 5] = ["=>", "#", "* ", ">", "```"];
+
+/// Checks that text will not be read as another line type
+pub(crate) fn validate_text(text: &str) -> crate::Result<()> {
+    if PREFIXES.iter().any(|prefix| text.starts_with(prefix)) {
+        return Err(crate::Error::AmbiguousText);
+    }
+
+    Ok(())
+}
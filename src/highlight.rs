@@ -0,0 +1,290 @@
+use crate::html::escape;
+use std::fmt;
+
+/// The lexical rules of a language, enough to find keywords, strings, comments and numbers
+pub(crate) struct Language {
+    /// Names used as alt text, the first of which is used in the `language-` class
+    names: &'static [&'static str],
+    keywords: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    /// Whether `'` delimits a single character rather than a string, as in `'a'` but not `'a`
+    char_quote: bool,
+}
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        names: &["rust", "rs"],
+        keywords: &[
+            "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+            "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod",
+            "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super",
+            "trait", "true", "type", "unsafe", "use", "where", "while",
+        ],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"'],
+        char_quote: true,
+    },
+    Language {
+        names: &["sh", "bash", "shell", "zsh"],
+        keywords: &[
+            "case", "do", "done", "elif", "else", "esac", "exit", "export", "fi", "for",
+            "function", "if", "in", "local", "readonly", "return", "then", "until", "while",
+        ],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        char_quote: false,
+    },
+    Language {
+        names: &["toml"],
+        keywords: &["false", "true"],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        char_quote: false,
+    },
+    Language {
+        names: &["json"],
+        keywords: &["false", "null", "true"],
+        line_comments: &[],
+        block_comment: None,
+        quotes: &['"'],
+        char_quote: false,
+    },
+    Language {
+        names: &["python", "py"],
+        keywords: &[
+            "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+            "elif", "else", "except", "False", "finally", "for", "from", "global", "if", "import",
+            "in", "is", "lambda", "None", "nonlocal", "not", "or", "pass", "raise", "return",
+            "True", "try", "while", "with", "yield",
+        ],
+        line_comments: &["#"],
+        block_comment: None,
+        quotes: &['"', '\''],
+        char_quote: false,
+    },
+    Language {
+        names: &["javascript", "js", "typescript", "ts"],
+        keywords: &[
+            "async",
+            "await",
+            "break",
+            "case",
+            "catch",
+            "class",
+            "const",
+            "continue",
+            "default",
+            "delete",
+            "do",
+            "else",
+            "export",
+            "extends",
+            "false",
+            "finally",
+            "for",
+            "function",
+            "if",
+            "import",
+            "in",
+            "instanceof",
+            "let",
+            "new",
+            "null",
+            "return",
+            "super",
+            "switch",
+            "this",
+            "throw",
+            "true",
+            "try",
+            "typeof",
+            "undefined",
+            "var",
+            "void",
+            "while",
+            "yield",
+        ],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"', '\'', '`'],
+        char_quote: false,
+    },
+    Language {
+        names: &["c", "h"],
+        keywords: &[
+            "auto", "break", "case", "char", "const", "continue", "default", "do", "double",
+            "else", "enum", "extern", "float", "for", "goto", "if", "int", "long", "register",
+            "return", "short", "signed", "sizeof", "static", "struct", "switch", "typedef",
+            "union", "unsigned", "void", "volatile", "while",
+        ],
+        line_comments: &["//"],
+        block_comment: Some(("/*", "*/")),
+        quotes: &['"'],
+        char_quote: true,
+    },
+];
+
+impl Language {
+    /// Finds a language named by the first word of alt text, ignoring case
+    pub(crate) fn find(alt: &str) -> Option<&'static Language> {
+        let name = alt.split_whitespace().next()?;
+        LANGUAGES.iter().find(|language| {
+            language
+                .names
+                .iter()
+                .any(|other| other.eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// Returns the name used in the `language-` class
+    pub(crate) fn name(&self) -> &'static str {
+        self.names[0]
+    }
+
+    /// Writes HTML-escaped code with tokens wrapped in `<span class="hl-…">` elements
+    pub(crate) fn highlight<W: fmt::Write + ?Sized>(&self, code: &str, w: &mut W) -> fmt::Result {
+        let mut rest = code;
+        let mut after_word = false;
+
+        while let Some(c) = rest.chars().next() {
+            let (len, class) = self.token(rest, c, after_word);
+            let (token, next) = rest.split_at(len);
+            match class {
+                Some(class) => write!(w, "<span class=\"hl-{}\">{}</span>", class, escape(token))?,
+                None => write!(w, "{}", escape(token))?,
+            }
+            after_word = is_word(token.chars().last().unwrap_or(' '));
+            rest = next;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the byte length and class of the token at the start of `rest`
+    fn token(&self, rest: &str, c: char, after_word: bool) -> (usize, Option<&'static str>) {
+        if let Some((open, close)) = self.block_comment {
+            if let Some(comment) = rest.strip_prefix(open) {
+                let len = comment
+                    .find(close)
+                    .map_or(rest.len(), |i| open.len() + i + close.len());
+                return (len, Some("comment"));
+            }
+        }
+
+        // A comment marker directly after a word is part of that word, as in `a#b`
+        if !after_word && self.line_comments.iter().any(|m| rest.starts_with(m)) {
+            return (rest.find('\n').unwrap_or(rest.len()), Some("comment"));
+        }
+
+        if c == '\'' && self.char_quote {
+            return match char_literal(rest) {
+                Some(len) => (len, Some("string")),
+                None => (1, None),
+            };
+        }
+
+        if self.quotes.contains(&c) {
+            return (string_literal(rest, c), Some("string"));
+        }
+
+        if c.is_ascii_digit() && !after_word {
+            let len = rest
+                .char_indices()
+                .find(|&(i, c)| {
+                    let fraction =
+                        c == '.' && rest[i + 1..].starts_with(|c: char| c.is_ascii_digit());
+                    !is_word(c) && !fraction
+                })
+                .map_or(rest.len(), |(i, _)| i);
+            return (len, Some("number"));
+        }
+
+        if is_word(c) {
+            let len = rest.find(|c| !is_word(c)).unwrap_or(rest.len());
+            let class = self.keywords.contains(&&rest[..len]).then_some("keyword");
+            return (len, class);
+        }
+
+        (c.len_utf8(), None)
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Returns the length of a string starting with `quote`, up to the closing quote or the end
+fn string_literal(rest: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (i, c) in rest.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == quote => return i + c.len_utf8(),
+            _ => {}
+        }
+    }
+    rest.len()
+}
+
+/// Returns the length of a character literal such as `'a'` or `'\n'`, if there is one
+fn char_literal(rest: &str) -> Option<usize> {
+    let mut chars = rest.char_indices().skip(1);
+    let (_, c) = chars.next()?;
+    if c == '\\' {
+        // Escapes are short, such as `'\u{1F600}'`
+        return chars.take(10).find(|&(_, c)| c == '\'').map(|(i, _)| i + 1);
+    }
+    match chars.next()? {
+        (i, '\'') => Some(i + 1),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn highlight(alt: &str, code: &str) -> String {
+        let mut s = String::new();
+        Language::find(alt)
+            .unwrap()
+            .highlight(code, &mut s)
+            .unwrap();
+        s
+    }
+
+    #[test]
+    fn rust() {
+        assert_eq!(
+            highlight(
+                "Rust",
+                "fn f<'a>(c: &'a str) -> u8 { b'\\n'; 1.5 } // <done>"
+            ),
+            "<span class=\"hl-keyword\">fn</span> f&lt;&#39;a&gt;(c: &amp;&#39;a str) -&gt; u8 { \
+             b<span class=\"hl-string\">&#39;\\n&#39;</span>; <span class=\"hl-number\">1.5</span> } \
+             <span class=\"hl-comment\">// &lt;done&gt;</span>"
+        );
+    }
+
+    #[test]
+    fn sh() {
+        assert_eq!(
+            highlight("sh script", "echo \"a\\\"b\" a#b # c\nexit 1"),
+            "echo <span class=\"hl-string\">&quot;a\\&quot;b&quot;</span> a#b \
+             <span class=\"hl-comment\"># c</span>\n\
+             <span class=\"hl-keyword\">exit</span> <span class=\"hl-number\">1</span>"
+        );
+    }
+
+    #[test]
+    fn unknown() {
+        assert!(Language::find("emoticon").is_none());
+        assert!(Language::find("").is_none());
+    }
+}
@@ -1,39 +1,433 @@
-use super::{Block, Level, Markup};
+use super::{Block, Level, Link, LinkRewrite, LinkStyle, Markup, Preformatted, Slugs};
+#[cfg(feature = "highlight")]
+use crate::highlight::Language;
+use std::fmt;
+use std::sync::Arc;
 
 /// An HTML formatter, available with the `html` feature
 ///
-/// Links are formatted as lists of links.
-pub struct Html;
+/// By default, links are formatted as lists of links
+/// and each text line is its own paragraph.
+/// Adjacent quote lines form one blockquote.
+/// The alt text of preformatted blocks labels the `<pre>` element.
+/// Links to images, audio and video can be rendered inline with [`Html::inline_media`].
+/// All text, including link URIs, is HTML-escaped.
+/// Links are only followed for `gemini`, `http`, `https`, `mailto` and `gopher` URIs
+/// and relative references; any other link, such as `javascript:`, is written as plain text.
+///
+/// # Example
+/// ```
+/// use mdiu::{Html, LinkStyle, TextStyle};
+///
+/// let html = Html::new()
+///     .link_style(LinkStyle::Paragraph)
+///     .text_style(TextStyle::Paragraphs);
+/// ```
+#[derive(Clone, Default)]
+pub struct Html {
+    link_style: LinkStyle,
+    text_style: TextStyle,
+    alt_style: AltStyle,
+    heading_style: HeadingStyle,
+    sections: bool,
+    #[cfg(feature = "highlight")]
+    highlight: bool,
+    media: Option<Classify>,
+    rewrite: Option<LinkRewrite>,
+}
+
+/// A function that classifies links for [`Html::inline_media`]
+type Classify = Arc<dyn Fn(&Link) -> Option<Media> + Send + Sync>;
+
+impl fmt::Debug for Html {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("Html");
+        debug
+            .field("link_style", &self.link_style)
+            .field("text_style", &self.text_style)
+            .field("alt_style", &self.alt_style)
+            .field("heading_style", &self.heading_style)
+            .field("sections", &self.sections);
+        #[cfg(feature = "highlight")]
+        debug.field("highlight", &self.highlight);
+        debug
+            // Functions cannot be formatted, so only whether one is set is shown
+            .field("media", &self.media.as_ref().map(|_| "Fn"))
+            .field("rewrite", &self.rewrite)
+            .finish()
+    }
+}
+
+/// How the [`Html`] formatter lays out text lines
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextStyle {
+    /// Each text line is its own paragraph
+    #[default]
+    Lines,
+    /// Adjacent text lines are joined into one paragraph with line breaks,
+    /// and empty lines start a new paragraph
+    ///
+    /// ```
+    /// # fn main() -> mdiu::Result<()> {
+    /// use mdiu::{Document, Html, TextStyle, ToMarkup};
+    ///
+    /// let html = Document::new()
+    ///     .text("one")
+    ///     .text("two")
+    ///     .empty()
+    ///     .text("three")
+    ///     .build()?
+    ///     .render(&Html::new().text_style(TextStyle::Paragraphs));
+    ///
+    /// assert_eq!(html, "<p>one<br>\ntwo</p>\n<p>three</p>\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    Paragraphs,
+}
+
+/// How the [`Html`] formatter presents the alt text of preformatted blocks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AltStyle {
+    /// The alt text labels the `<pre>` element for assistive technology
+    ///
+    /// ```html
+    /// <pre aria-label="emoticon">
+    /// @_@
+    /// </pre>
+    /// ```
+    #[default]
+    AriaLabel,
+    /// The `<pre>` element is wrapped in a `<figure>` with the alt text as a visible caption
+    ///
+    /// ```html
+    /// <figure>
+    /// <figcaption>emoticon</figcaption>
+    /// <pre>
+    /// @_@
+    /// </pre>
+    /// </figure>
+    /// ```
+    Figure,
+}
+
+/// How the [`Html`] formatter identifies headings
+///
+/// Ids are the [`slug`] of the heading text, made unique with [`Slugs`],
+/// so the same ids can be generated for fragment links from other documents.
+///
+/// [`slug`]: crate::slug
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HeadingStyle {
+    /// Headings have no `id`
+    #[default]
+    Plain,
+    /// Headings have an `id`
+    ///
+    /// ```html
+    /// <h2 id="notes">Notes</h2>
+    /// ```
+    Ids,
+    /// Headings have an `id` and link to themselves
+    ///
+    /// ```html
+    /// <h2 id="notes"><a href="#notes">Notes</a></h2>
+    /// ```
+    SelfLinks,
+}
+
+/// The kind of media a link points to, for [`Html::inline_media`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Media {
+    /// Rendered as an `<img>` with the link label as alt text
+    Image,
+    /// Rendered as an `<audio>` player with the link label as caption
+    Audio,
+    /// Rendered as a `<video>` player with the link label as caption
+    Video,
+}
+
+impl Media {
+    /// Classifies a file extension such as `png` or `MP3`
+    pub fn from_extension(extension: &str) -> Option<Media> {
+        const IMAGE: &[&str] = &[
+            "apng", "avif", "bmp", "gif", "jpeg", "jpg", "png", "svg", "webp",
+        ];
+        const AUDIO: &[&str] = &["aac", "flac", "m4a", "mp3", "oga", "ogg", "opus", "wav"];
+        const VIDEO: &[&str] = &["m4v", "mov", "mp4", "ogv", "webm"];
+
+        let is = |extensions: &[&str]| extensions.iter().any(|e| e.eq_ignore_ascii_case(extension));
+        if is(IMAGE) {
+            Some(Media::Image)
+        } else if is(AUDIO) {
+            Some(Media::Audio)
+        } else if is(VIDEO) {
+            Some(Media::Video)
+        } else {
+            None
+        }
+    }
+
+    /// Classifies a link by the extension of its URI path, ignoring any query
+    ///
+    /// ```
+    /// use http::uri::Uri;
+    /// use mdiu::{Link, Media};
+    ///
+    /// assert_eq!(Media::classify(&Uri::from_static("/cat.png?v=2").into()), Some(Media::Image));
+    /// assert_eq!(Media::classify(&Uri::from_static("gemini://host/").into()), None);
+    /// ```
+    pub fn classify(link: &Link) -> Option<Media> {
+        let uri = link.uri();
+        // URIs such as `mailto:` name no file
+        if uri.scheme().is_some() && uri.authority().is_none() {
+            return None;
+        }
+        let name = uri.path().rsplit('/').next()?;
+        let (_, extension) = name.rsplit_once('.')?;
+        Media::from_extension(extension)
+    }
+}
+
+impl Html {
+    /// Constructs a new `Html` formatter with the default configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how links are laid out
+    pub fn link_style(mut self, link_style: LinkStyle) -> Self {
+        self.link_style = link_style;
+        self
+    }
+
+    /// Sets how text lines are laid out
+    pub fn text_style(mut self, text_style: TextStyle) -> Self {
+        self.text_style = text_style;
+        self
+    }
+
+    /// Sets how the alt text of preformatted blocks is presented
+    pub fn alt_style(mut self, alt_style: AltStyle) -> Self {
+        self.alt_style = alt_style;
+        self
+    }
+
+    /// Sets how headings are identified
+    pub fn heading_style(mut self, heading_style: HeadingStyle) -> Self {
+        self.heading_style = heading_style;
+        self
+    }
+
+    /// Wraps each heading and the blocks that belong to it in nested `<section>` elements
+    ///
+    /// A section ends at the next heading of the same or a higher level,
+    /// following the tree of an [`Outline`].
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> mdiu::Result<()> {
+    /// use mdiu::{Document, Html, ToMarkup};
+    ///
+    /// let html = Document::new()
+    ///     .h1("manual")
+    ///     .h2("usage")
+    ///     .text("run it")
+    ///     .build()?
+    ///     .render(&Html::new().sections(true));
+    ///
+    /// assert_eq!(
+    ///     html,
+    ///     "<section>\n<h1>manual</h1>\n\
+    ///      <section>\n<h2>usage</h2>\n<p>run it</p>\n</section>\n\
+    ///      </section>\n"
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Outline`]: crate::Outline
+    pub fn sections(mut self, sections: bool) -> Self {
+        self.sections = sections;
+        self
+    }
+
+    /// Enables syntax highlighting of preformatted blocks, available with the `highlight` feature
+    ///
+    /// The first word of the alt text is used as the language, such as `rust`, `sh` or `toml`.
+    /// Code is wrapped in `<code class="language-…">`, with keywords, strings, comments
+    /// and numbers in `<span>`s with the classes `hl-keyword`, `hl-string`, `hl-comment`
+    /// and `hl-number`. Preformatted blocks in other languages are formatted as plain text.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> mdiu::Result<()> {
+    /// use mdiu::{Document, Html, ToMarkup};
+    ///
+    /// let html = Document::new()
+    ///     .preformatted_with_alt("let x = 1;", "rust")
+    ///     .build()?
+    ///     .render(&Html::new().highlight(true));
+    ///
+    /// assert_eq!(
+    ///     html,
+    ///     "<pre aria-label=\"rust\"><code class=\"language-rust\">\
+    ///      <span class=\"hl-keyword\">let</span> x = <span class=\"hl-number\">1</span>;\
+    ///      </code></pre>\n"
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "highlight")]
+    pub fn highlight(mut self, highlight: bool) -> Self {
+        self.highlight = highlight;
+        self
+    }
+
+    /// Renders links to media inline, as classified by `classify`
+    ///
+    /// [`Media::classify`] uses the extension of the URI path,
+    /// and a custom closure can fall back to it.
+    /// Media links are never grouped into lists of links.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> mdiu::Result<()> {
+    /// use http::uri::Uri;
+    /// use mdiu::{Document, Html, Media, ToMarkup};
+    ///
+    /// let audio = vec!["/purr".to_string()];
+    /// let html = Document::new()
+    ///     .link_with_label(Uri::from_static("cat.jpg"), "a cat")
+    ///     .link_with_label(Uri::from_static("/purr"), "purring")
+    ///     .build()?
+    ///     .render(&Html::new().inline_media(move |link| {
+    ///         if audio.iter().any(|path| path == link.uri().path()) {
+    ///             Some(Media::Audio)
+    ///         } else {
+    ///             Media::classify(link)
+    ///         }
+    ///     }));
+    ///
+    /// assert_eq!(
+    ///     html,
+    ///     r#"<figure>
+    /// <img src="cat.jpg" alt="a cat">
+    /// </figure>
+    /// <figure>
+    /// <audio controls src="/purr"><a href="/purr">purring</a></audio>
+    /// <figcaption>purring</figcaption>
+    /// </figure>
+    /// "#
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn inline_media<F>(mut self, classify: F) -> Self
+    where
+        F: Fn(&Link) -> Option<Media> + Send + Sync + 'static,
+    {
+        self.media = Some(Arc::new(classify));
+        self
+    }
+
+    /// Rewrites link URIs with a [`LinkRewrite`] policy
+    ///
+    /// Unlabeled links keep the original URI as their text.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> mdiu::Result<()> {
+    /// use http::uri::Uri;
+    /// use mdiu::{Document, GeminiLinks, Html, LinkRewrite, LinkStyle, ToMarkup};
+    ///
+    /// let html = Document::new()
+    ///     .link_with_label(Uri::from_static("about.gmi"), "about")
+    ///     .link(Uri::from_static("gemini://example.org/"))
+    ///     .build()?
+    ///     .render(
+    ///         &Html::new()
+    ///             .link_style(LinkStyle::Paragraph)
+    ///             .rewrite(LinkRewrite::new().gemini(GeminiLinks::External)),
+    ///     );
+    ///
+    /// assert_eq!(
+    ///     html,
+    ///     "<p><a href=\"about.html\">about</a></p>\n\
+    ///      <p><a href=\"gemini://example.org/\" rel=\"external\">gemini://example.org/</a></p>\n"
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rewrite(mut self, rewrite: LinkRewrite) -> Self {
+        self.rewrite = Some(rewrite);
+        self
+    }
+
+    fn media(&self, link: &Link) -> Option<Media> {
+        self.href(link)?;
+        self.media.as_ref().and_then(|classify| classify(link))
+    }
+
+    /// Returns the `href` of a link and any further attributes,
+    /// or `None` if the scheme is not allowed
+    fn href(&self, link: &Link) -> Option<(String, &'static str)> {
+        let (href, rel) = match &self.rewrite {
+            Some(rewrite) => {
+                let rel = if rewrite.is_external(link.uri()) {
+                    " rel=\"external\""
+                } else {
+                    ""
+                };
+                (rewrite.uri(link.uri()), rel)
+            }
+            None => (link.uri().clone(), ""),
+        };
+        match href.scheme() {
+            Some(scheme) if !SCHEMES.iter().any(|s| s.eq_ignore_ascii_case(scheme)) => None,
+            _ => Some((href.to_string(), rel)),
+        }
+    }
+}
 
 impl Markup for Html {
-    fn markup(blocks: &[Block]) -> String {
-        let mut iter = blocks.iter();
+    fn fmt_markup<'a, I, W>(&self, blocks: I, w: &mut W) -> fmt::Result
+    where
+        I: IntoIterator<Item = &'a Block>,
+        W: fmt::Write + ?Sized,
+    {
+        let mut iter = blocks.into_iter();
         let mut block = iter.next();
-        let mut in_list: Option<List> = None;
+        let mut open: Option<Group> = None;
+        // Whether a line break is needed before the next line of a paragraph or quote
+        let mut separate = false;
+        let mut slugs = Slugs::new();
+        // The heading level of each open section
+        let mut sections: Vec<Level> = Vec::new();
 
         std::iter::from_fn(|| {
-            let intermediate = match (in_list.as_ref(), block) {
-                (None, Some(Block::Link(_))) => {
-                    in_list = Some(List::Link);
-                    Intermediate::OpenList
+            let intermediate = match (open, block.map(|b| self.group(b))) {
+                (None, Some(Some(group))) => {
+                    open = Some(group);
+                    separate = false;
+                    Intermediate::Open(group)
                 }
-                (None, Some(Block::ListItem(_))) => {
-                    in_list = Some(List::Item);
-                    Intermediate::OpenList
+                (Some(group), None) => {
+                    open = None;
+                    Intermediate::Close(group)
                 }
-                (Some(_), None) => {
-                    in_list = None;
-                    Intermediate::CloseList
+                (Some(group), Some(next)) if next != Some(group) => {
+                    open = None;
+                    Intermediate::Close(group)
                 }
-                (Some(List::Link), Some(b)) if !matches!(b, Block::Link(_)) => {
-                    in_list = None;
-                    Intermediate::CloseList
+                (Some(Group::Paragraph | Group::Quote), Some(_)) if separate => {
+                    separate = false;
+                    Intermediate::LineBreak
                 }
-                (Some(List::Item), Some(b)) if !matches!(b, Block::ListItem(_)) => {
-                    in_list = None;
-                    Intermediate::CloseList
+                (_, Some(_)) => {
+                    separate = true;
+                    Intermediate::Block(block.unwrap())
                 }
-                (_, Some(block)) => Intermediate::Block(block),
                 (None, None) => return None,
             };
 
@@ -43,46 +437,406 @@ impl Markup for Html {
 
             Some(intermediate)
         })
-        .map(|intermediate| match intermediate {
-            Intermediate::Block(block) => generate(block),
-            Intermediate::OpenList => "<ul>\n".to_string(),
-            Intermediate::CloseList => "</ul>\n".to_string(),
-        })
-        .collect::<String>()
+        .try_for_each(|intermediate| match intermediate {
+            Intermediate::Block(block) => {
+                if let (true, Block::Heading(level, _)) = (self.sections, block) {
+                    while sections.last().is_some_and(|open| open >= level) {
+                        sections.pop();
+                        w.write_str("</section>\n")?;
+                    }
+                    sections.push(*level);
+                    w.write_str("<section>\n")?;
+                }
+                self.generate(block, &mut slugs, w)
+            }
+            Intermediate::Open(Group::Paragraph) => w.write_str("<p>"),
+            Intermediate::Open(Group::Quote) => w.write_str("<blockquote>"),
+            Intermediate::Open(_) => w.write_str("<ul>\n"),
+            Intermediate::Close(Group::Paragraph) => w.write_str("</p>\n"),
+            Intermediate::Close(Group::Quote) => w.write_str("</blockquote>\n"),
+            Intermediate::Close(_) => w.write_str("</ul>\n"),
+            Intermediate::LineBreak => w.write_str("<br>\n"),
+        })?;
+
+        sections
+            .iter()
+            .try_for_each(|_| w.write_str("</section>\n"))
     }
 }
 
-enum List {
-    Link,
-    Item,
+/// Adjacent blocks that are wrapped in a single element
+#[derive(Clone, Copy, PartialEq)]
+enum Group {
+    LinkList,
+    ItemList,
+    Paragraph,
+    Quote,
 }
 
 enum Intermediate<'a> {
     Block(&'a Block),
-    OpenList,
-    CloseList,
+    Open(Group),
+    Close(Group),
+    LineBreak,
 }
 
-fn generate(block: &Block) -> String {
-    match block {
-        Block::Text(text) => format!("<p>{}</p>\n", text),
-        Block::Link(link) => match link.label() {
-            Some(label) => {
-                format!("<li><a href=\"{}\">{}</a></li>\n", link.uri(), label)
+impl Html {
+    fn group(&self, block: &Block) -> Option<Group> {
+        match block {
+            Block::Link(link) if self.media(link).is_some() => None,
+            Block::Link(_) if self.link_style == LinkStyle::List => Some(Group::LinkList),
+            Block::ListItem(_) => Some(Group::ItemList),
+            Block::Text(_) if self.text_style == TextStyle::Paragraphs => Some(Group::Paragraph),
+            Block::Quote(_) => Some(Group::Quote),
+            _ => None,
+        }
+    }
+}
+
+impl Html {
+    fn generate<W: fmt::Write + ?Sized>(
+        &self,
+        block: &Block,
+        slugs: &mut Slugs,
+        w: &mut W,
+    ) -> fmt::Result {
+        match block {
+            Block::Text(text) => match self.text_style {
+                TextStyle::Lines => writeln!(w, "<p>{}</p>", escape(text)),
+                TextStyle::Paragraphs => write!(w, "{}", escape(text)),
+            },
+            Block::Link(link) => {
+                if let Some(media) = self.media(link) {
+                    return self.media_link(link, media, w);
+                }
+                let uri = link.uri().to_string();
+                let (open, close) = match self.link_style {
+                    LinkStyle::List => ("<li>", "</li>"),
+                    LinkStyle::Paragraph => ("<p>", "</p>"),
+                };
+                let text = link.label().as_ref().map_or(uri.as_str(), |l| l.as_ref());
+                match self.href(link) {
+                    Some((href, attributes)) => writeln!(
+                        w,
+                        "{}<a href=\"{}\"{}>{}</a>{}",
+                        open,
+                        escape(&href),
+                        attributes,
+                        escape(text),
+                        close
+                    ),
+                    None => writeln!(w, "{}{}{}", open, escape(text), close),
+                }
+            }
+            Block::Heading(level, text) => {
+                let tag = match level {
+                    Level::One => "h1",
+                    Level::Two => "h2",
+                    Level::Three => "h3",
+                };
+                match self.heading_style {
+                    HeadingStyle::Plain => writeln!(w, "<{0}>{1}</{0}>", tag, escape(text)),
+                    HeadingStyle::Ids => {
+                        let id = slugs.unique(text.as_ref());
+                        writeln!(
+                            w,
+                            "<{0} id=\"{1}\">{2}</{0}>",
+                            tag,
+                            escape(&id),
+                            escape(text)
+                        )
+                    }
+                    HeadingStyle::SelfLinks => {
+                        let id = slugs.unique(text.as_ref());
+                        writeln!(
+                            w,
+                            "<{0} id=\"{1}\"><a href=\"#{1}\">{2}</a></{0}>",
+                            tag,
+                            escape(&id),
+                            escape(text)
+                        )
+                    }
+                }
             }
-            None => {
-                format!("<li><a href=\"{0}\">{0}</a></li>\n", link.uri())
+            Block::ListItem(text) => {
+                writeln!(w, "<li>{}</li>", escape(text))
             }
-        },
-        Block::Heading(Level::One, text) => format!("<h1>{}</h1>\n", text),
-        Block::Heading(Level::Two, text) => format!("<h2>{}</h2>\n", text),
-        Block::Heading(Level::Three, text) => format!("<h3>{}</h3>\n", text),
-        Block::ListItem(text) => {
-            format!("<li>{}</li>\n", text)
+            Block::Quote(text) => write!(w, "{}", escape(text)),
+            Block::Preformatted(pre) => self.preformatted(pre, w),
+            // Empty lines only end paragraphs
+            Block::Empty => Ok(()),
         }
-        Block::Quote(text) => format!("<blockquote>{}</blockquote>\n", text),
-        Block::Preformatted(pre) => format!("<pre>\n{}\n</pre>\n", pre.text()),
-        // TODO
-        Block::Empty => "".to_string(),
+    }
+
+    fn media_link<W: fmt::Write + ?Sized>(
+        &self,
+        link: &Link,
+        media: Media,
+        w: &mut W,
+    ) -> fmt::Result {
+        // Media links always have an allowed scheme, see `Html::media`
+        let (src, attributes) = self.href(link).unwrap_or_default();
+        let uri = link.uri().to_string();
+        let label = link.label().as_ref().map(|label| label.as_ref());
+        w.write_str("<figure>\n")?;
+        let element = match media {
+            Media::Image => {
+                return writeln!(
+                    w,
+                    "<img src=\"{}\" alt=\"{}\">\n</figure>",
+                    escape(&src),
+                    escape(label.unwrap_or_default())
+                );
+            }
+            Media::Audio => "audio",
+            Media::Video => "video",
+        };
+        // The link is shown by browsers that cannot play the media
+        writeln!(
+            w,
+            "<{0} controls src=\"{1}\"><a href=\"{1}\"{2}>{3}</a></{0}>",
+            element,
+            escape(&src),
+            attributes,
+            escape(label.unwrap_or(&uri))
+        )?;
+        if let Some(label) = label {
+            writeln!(w, "<figcaption>{}</figcaption>", escape(label))?;
+        }
+        w.write_str("</figure>\n")
+    }
+
+    fn preformatted<W: fmt::Write + ?Sized>(&self, pre: &Preformatted, w: &mut W) -> fmt::Result {
+        let alt = pre.alt().as_ref().map(|alt| alt.as_ref());
+        match (alt, self.alt_style) {
+            (Some(alt), AltStyle::AriaLabel) => write!(w, "<pre aria-label=\"{}\">", escape(alt))?,
+            (Some(alt), AltStyle::Figure) => write!(
+                w,
+                "<figure>\n<figcaption>{}</figcaption>\n<pre>",
+                escape(alt)
+            )?,
+            (None, _) => w.write_str("<pre>")?,
+        }
+
+        // Known languages are highlighted, and anything else is plain text
+        #[cfg(feature = "highlight")]
+        let highlighted = match alt.filter(|_| self.highlight).and_then(Language::find) {
+            Some(language) => {
+                write!(w, "<code class=\"language-{}\">", language.name())?;
+                language.highlight(pre.text(), w)?;
+                w.write_str("</code></pre>\n")?;
+                true
+            }
+            None => false,
+        };
+        #[cfg(not(feature = "highlight"))]
+        let highlighted = false;
+
+        if !highlighted {
+            write!(w, "\n{}\n</pre>\n", escape(pre.text()))?;
+        }
+
+        match (alt, self.alt_style) {
+            (Some(_), AltStyle::Figure) => w.write_str("</figure>\n"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The schemes of links that are followed, besides relative references
+const SCHEMES: [&str; 5] = ["gemini", "http", "https", "mailto", "gopher"];
+
+/// Escapes text for use in HTML elements and quoted attribute values
+pub(crate) fn escape<T: AsRef<str> + ?Sized>(text: &T) -> Escape<'_> {
+    Escape(text.as_ref())
+}
+
+pub(crate) struct Escape<'a>(&'a str);
+
+impl fmt::Display for Escape<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rest = self.0;
+        while let Some(i) = rest.find(['&', '<', '>', '"', '\'']) {
+            f.write_str(&rest[..i])?;
+            f.write_str(match rest.as_bytes()[i] {
+                b'&' => "&amp;",
+                b'<' => "&lt;",
+                b'>' => "&gt;",
+                b'"' => "&quot;",
+                _ => "&#39;",
+            })?;
+            rest = &rest[i + 1..];
+        }
+        f.write_str(rest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use http::uri::Uri;
+
+    #[test]
+    fn paragraphs() {
+        let html = Document::new()
+            .text("one")
+            .text("two")
+            .list_item("item")
+            .text("three")
+            .empty()
+            .empty()
+            .h1("heading")
+            .text("four")
+            .build()
+            .unwrap()
+            .render(&Html::new().text_style(TextStyle::Paragraphs));
+
+        let expected = r#"<p>one<br>
+two</p>
+<ul>
+<li>item</li>
+</ul>
+<p>three</p>
+<h1>heading</h1>
+<p>four</p>
+"#;
+        assert_eq!(html, expected);
+    }
+
+    #[test]
+    fn quotes() {
+        let html = Document::new()
+            .quote("one")
+            .quote("two")
+            .text("text")
+            .quote("three")
+            .build()
+            .unwrap()
+            .to_markup::<Html>();
+
+        let expected = r#"<blockquote>one<br>
+two</blockquote>
+<p>text</p>
+<blockquote>three</blockquote>
+"#;
+        assert_eq!(html, expected);
+    }
+
+    #[test]
+    fn alt_text() {
+        let html = Document::new()
+            .preformatted_with_alt("fn main() {}", "rust")
+            .build()
+            .unwrap()
+            .render(&Html::new().alt_style(AltStyle::Figure));
+
+        let expected = r#"<figure>
+<figcaption>rust</figcaption>
+<pre>
+fn main() {}
+</pre>
+</figure>
+"#;
+        assert_eq!(html, expected);
+    }
+
+    #[test]
+    fn inline_media() {
+        let html = Document::new()
+            .link(Uri::from_static("/a"))
+            .link(Uri::from_static("gemini://host/clip.WebM"))
+            .link_with_label(Uri::from_static("mailto:me@host.png"), "mail")
+            .link_with_label(Uri::from_static("cat.png"), "\"cat\"")
+            .link_with_label(Uri::from_static("javascript:alert(1).png"), "x")
+            .build()
+            .unwrap()
+            .render(&Html::new().inline_media(Media::classify));
+
+        let expected = r#"<ul>
+<li><a href="/a">/a</a></li>
+</ul>
+<figure>
+<video controls src="gemini://host/clip.WebM"><a href="gemini://host/clip.WebM">gemini://host/clip.WebM</a></video>
+</figure>
+<ul>
+<li><a href="mailto:me@host.png">mail</a></li>
+</ul>
+<figure>
+<img src="cat.png" alt="&quot;cat&quot;">
+</figure>
+<ul>
+<li>x</li>
+</ul>
+"#;
+        assert_eq!(html, expected);
+    }
+
+    #[test]
+    fn heading_ids() {
+        let doc = Document::new()
+            .h1("Notes")
+            .h2("<Notes>")
+            .h3("!")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            doc.render(&Html::new().heading_style(HeadingStyle::Ids)),
+            "<h1 id=\"notes\">Notes</h1>\n\
+             <h2 id=\"notes-1\">&lt;Notes&gt;</h2>\n\
+             <h3 id=\"section\">!</h3>\n"
+        );
+        assert!(doc
+            .render(&Html::new().heading_style(HeadingStyle::SelfLinks))
+            .starts_with("<h1 id=\"notes\"><a href=\"#notes\">Notes</a></h1>\n"));
+    }
+
+    #[cfg(feature = "highlight")]
+    #[test]
+    fn highlight_unknown_language() {
+        let html = Document::new()
+            .preformatted_with_alt("<@_@>", "emoticon")
+            .build()
+            .unwrap()
+            .render(&Html::new().highlight(true));
+
+        assert_eq!(html, "<pre aria-label=\"emoticon\">\n&lt;@_@&gt;\n</pre>\n");
+    }
+
+    #[test]
+    fn escaping() {
+        let html = Document::new()
+            .h1("<script>alert(1)</script>")
+            .text("Tom & \"Jerry\"")
+            .link_with_label(Uri::from_static("/?a=1&b=2"), "<b>bold</b>")
+            .link_with_label(Uri::from_static("javascript:alert(1)"), "click")
+            .link(Uri::from_static("JavaScript:alert(1)"))
+            .quote("it's")
+            .preformatted("</pre><img src=x onerror=alert(1)>")
+            .build()
+            .unwrap()
+            .to_markup::<Html>();
+
+        let expected = r#"<h1>&lt;script&gt;alert(1)&lt;/script&gt;</h1>
+<p>Tom &amp; &quot;Jerry&quot;</p>
+<ul>
+<li><a href="/?a=1&amp;b=2">&lt;b&gt;bold&lt;/b&gt;</a></li>
+<li>click</li>
+<li>JavaScript:alert(1)</li>
+</ul>
+<blockquote>it&#39;s</blockquote>
+<pre>
+&lt;/pre&gt;&lt;img src=x onerror=alert(1)&gt;
+</pre>
+"#;
+        assert_eq!(html, expected);
+
+        #[cfg(feature = "parsing")]
+        assert_eq!(
+            parse("=> javascript:alert(1) hi\n")
+                .unwrap()
+                .to_markup::<Html>(),
+            "<ul>\n<li>hi</li>\n</ul>\n"
+        );
     }
 }
@@ -1,6 +1,7 @@
 //! Build documents with [Gemtext]
 //!
 //! `mdiu` provides a correct and flexible approach to creating small documents with Gemtext.
+//! Links can be rewritten for publishing on the web with a [`LinkRewrite`].
 //!
 //! Named after the [Manual Data Insertion Unit], part of Gemini's on-board computer.
 //!
@@ -46,7 +47,9 @@
 //! * `html`
 //! * `markdown`
 //!
-//! A Gemtext `parsing` feature is planned but not yet implemented.
+//! The `highlight` feature adds syntax highlighting of preformatted blocks to `Html` output.
+//!
+//! Parsing Gemtext into [`Block`]s is available with the `parsing` feature.
 //!
 //! # Alternatives
 //!
@@ -58,6 +61,8 @@
 //! [Gemtext]: https://gemini.circumlunar.space/docs/gemtext.gmi
 //! [Manual Data Insertion Unit]: https://web.archive.org/web/20220201083102/https://www.ibiblio.org/apollo/Gemini.html
 
+use std::{fmt, io};
+
 mod content;
 pub use content::Content;
 
@@ -65,10 +70,25 @@ mod document;
 pub use document::Document;
 
 mod error;
-pub use error::{Error, Result};
+pub use error::{Error, Field, Report, Result};
 
 mod link;
-pub use link::Link;
+pub use link::{Link, UriRef};
+
+mod shift;
+pub use shift::{shift_headings, Overflow};
+
+mod slug;
+pub use slug::{slug, Slugs};
+
+mod section;
+pub use section::{Outline, Section};
+
+mod toc;
+pub use toc::{Toc, TocEntry};
+
+mod rewrite;
+pub use rewrite::{GeminiLinks, LinkRewrite};
 
 mod preformatted;
 pub use preformatted::Preformatted;
@@ -76,15 +96,28 @@ pub use preformatted::Preformatted;
 mod gemtext;
 pub use gemtext::Gemtext;
 
+#[cfg(feature = "parsing")]
+mod parser;
+#[cfg(feature = "parsing")]
+pub use parser::{parse, parse_spanned, Parser, Span, Spanned, SpannedBlock};
+
 #[cfg(feature = "html")]
 mod html;
 #[cfg(feature = "html")]
-pub use html::Html;
+pub use html::{AltStyle, HeadingStyle, Html, Media, TextStyle};
+
+#[cfg(feature = "highlight")]
+mod highlight;
+
+#[cfg(feature = "html")]
+mod page;
+#[cfg(feature = "html")]
+pub use page::{HtmlPage, Stylesheet};
 
 #[cfg(feature = "markdown")]
 mod markdown;
 #[cfg(feature = "markdown")]
-pub use markdown::Markdown;
+pub use markdown::{CodeStyle, Markdown};
 
 /// A Gemtext element
 #[derive(Clone, Debug)]
@@ -98,54 +131,285 @@ pub enum Block {
     Empty,
 }
 
+impl Block {
+    /// Returns the kind of the block
+    pub fn kind(&self) -> BlockKind {
+        match self {
+            Block::Text(_) => BlockKind::Text,
+            Block::Link(_) => BlockKind::Link,
+            Block::Heading(..) => BlockKind::Heading,
+            Block::ListItem(_) => BlockKind::ListItem,
+            Block::Quote(_) => BlockKind::Quote,
+            Block::Preformatted(_) => BlockKind::Preformatted,
+            Block::Empty => BlockKind::Empty,
+        }
+    }
+}
+
+/// The kind of a [`Block`], without its contents
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlockKind {
+    Text,
+    Link,
+    Heading,
+    ListItem,
+    Quote,
+    Preformatted,
+    Empty,
+}
+
+impl fmt::Display for BlockKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BlockKind::Text => "text line",
+            BlockKind::Link => "link",
+            BlockKind::Heading => "heading",
+            BlockKind::ListItem => "list item",
+            BlockKind::Quote => "quote",
+            BlockKind::Preformatted => "preformatted block",
+            BlockKind::Empty => "empty line",
+        })
+    }
+}
+
+/// How the `Html` and `Markdown` formatters lay out links
+///
+/// Gemtext links are always on their own line.
+#[cfg(any(feature = "html", feature = "markdown"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LinkStyle {
+    /// Adjacent links are grouped into a list
+    #[default]
+    List,
+    /// Each link is its own paragraph
+    Paragraph,
+}
+
 /// Heading level of a [`Block::Heading`]
-#[derive(Clone, Debug)]
+///
+/// Levels are ordered from [`Level::One`], the highest, to [`Level::Three`],
+/// and convert to and from the number of `#` of the heading.
+///
+/// # Example
+/// ```
+/// # fn main() -> mdiu::Result<()> {
+/// use mdiu::Level;
+///
+/// assert!(Level::One < Level::Two);
+/// assert_eq!(u8::from(Level::Two), 2);
+/// assert_eq!(Level::try_from(3)?, Level::Three);
+/// assert!(Level::try_from(4).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Level {
     One,
     Two,
     Three,
 }
 
+impl From<Level> for u8 {
+    fn from(level: Level) -> u8 {
+        match level {
+            Level::One => 1,
+            Level::Two => 2,
+            Level::Three => 3,
+        }
+    }
+}
+
+impl TryFrom<u8> for Level {
+    type Error = Error;
+
+    fn try_from(n: u8) -> Result<Level> {
+        match n {
+            1 => Ok(Level::One),
+            2 => Ok(Level::Two),
+            3 => Ok(Level::Three),
+            _ => Err(Error::InvalidLevel),
+        }
+    }
+}
+
 /// Format an iterator of [`Block`]s
 ///
+/// Formatters are values that may carry configuration.
+/// They implement [`fmt_markup`], which writes straight into a [`fmt::Write`];
+/// [`render`] and [`write_markup`] are built on it.
+///
+/// Formatters with a [`Default`] configuration can also be used with [`markup`].
+///
 /// # Example
 ///
 /// See example usage in [crate documentation](./index.html#examples).
+///
+/// Write to a file, or any other [`io::Write`], without building a `String`
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use mdiu::{Document, Gemtext, Markup};
+/// use std::{fs::File, io::BufWriter};
+///
+/// let doc = Document::new().h1("my gemlog").build()?;
+/// let file = BufWriter::new(File::create("index.gmi")?);
+///
+/// Gemtext.write_markup(&doc, file)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`fmt_markup`]: #tymethod.fmt_markup
+/// [`markup`]: #method.markup
+/// [`render`]: #method.render
+/// [`write_markup`]: #method.write_markup
 pub trait Markup {
     // This takes an iter of Blocks so the formatter can handle adjacent Blocks,
     // for example wrapping lists with <ul> in HTML
-    fn markup(blocks: &[Block]) -> String;
+    fn fmt_markup<'a, I, W>(&self, blocks: I, w: &mut W) -> fmt::Result
+    where
+        I: IntoIterator<Item = &'a Block>,
+        W: fmt::Write + ?Sized;
+
+    /// Formats blocks into a `String`
+    fn render<'a, I>(&self, blocks: I) -> String
+    where
+        I: IntoIterator<Item = &'a Block>,
+    {
+        let mut s = String::new();
+        self.fmt_markup(blocks, &mut s)
+            .expect("writing to a String cannot fail");
+        s
+    }
+
+    /// Writes formatted blocks to an [`io::Write`]
+    ///
+    /// # Errors
+    /// Returns the first error from the writer.
+    fn write_markup<'a, I, W>(&self, blocks: I, writer: W) -> io::Result<()>
+    where
+        I: IntoIterator<Item = &'a Block>,
+        W: io::Write,
+    {
+        let mut adapter = IoAdapter {
+            writer,
+            error: Ok(()),
+        };
+        match self.fmt_markup(blocks, &mut adapter) {
+            Ok(()) => Ok(()),
+            Err(_) => adapter.error.and(Err(io::Error::other("formatter error"))),
+        }
+    }
+
+    /// Formats blocks into a `String` with the default configuration
+    fn markup<'a, I>(blocks: I) -> String
+    where
+        I: IntoIterator<Item = &'a Block>,
+        Self: Default,
+    {
+        Self::default().render(blocks)
+    }
+}
+
+/// Writes to an [`io::Write`] through [`fmt::Write`], keeping the underlying error
+struct IoAdapter<W> {
+    writer: W,
+    error: io::Result<()>,
+}
+
+impl<W: io::Write> fmt::Write for IoAdapter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.writer.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Err(e);
+            fmt::Error
+        })
+    }
 }
 
 /// Create [`Markup`]-formatted strings
 ///
-/// This trait is sealed and cannot be implemented for types outside this crate.
+/// This trait is implemented for anything that iterates over `&Block`,
+/// such as `Vec<Block>`, `VecDeque<Block>` or a filtered iterator.
+/// Custom containers can use it by implementing [`IntoIterator`] for a reference to the container.
 ///
 /// # Example
 ///
 /// See example usage in [crate documentation](./index.html#examples).
-pub trait ToMarkup: private::Sealed {
+///
+/// Render with a configured formatter
+/// ```
+/// # fn main() -> mdiu::Result<()> {
+/// # #[cfg(feature = "html")] {
+/// use http::uri::Uri;
+/// use mdiu::{Document, Html, LinkStyle, ToMarkup};
+///
+/// let doc = Document::new().link(Uri::from_static("/")).build()?;
+/// let html = doc.render(&Html::new().link_style(LinkStyle::Paragraph));
+///
+/// assert_eq!(html, "<p><a href=\"/\">/</a></p>\n");
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Render a lazily filtered stream of blocks
+/// ```
+/// # fn main() -> mdiu::Result<()> {
+/// use mdiu::{Block, Document, Gemtext, ToMarkup};
+///
+/// let doc = Document::new().h1("my gemlog").empty().text("welcome").build()?;
+/// let gemtext = doc
+///     .iter()
+///     .filter(|block| !matches!(block, Block::Empty))
+///     .to_markup::<Gemtext>();
+///
+/// assert_eq!(gemtext, "# my gemlog\nwelcome\n");
+/// # Ok(())
+/// # }
+/// ```
+pub trait ToMarkup {
+    /// Formats blocks with the default configuration of a formatter
     fn to_markup<F>(self) -> String
+    where
+        F: Markup + Default;
+
+    /// Formats blocks with a formatter
+    fn render<F>(self, formatter: &F) -> String
     where
         F: Markup;
+
+    /// Writes blocks formatted with a formatter to an [`io::Write`]
+    ///
+    /// # Errors
+    /// Returns the first error from the writer.
+    fn write_markup<F, W>(self, formatter: &F, writer: W) -> io::Result<()>
+    where
+        F: Markup,
+        W: io::Write;
 }
 
-impl<T> ToMarkup for T
+impl<'a, T> ToMarkup for T
 where
-    T: std::ops::Deref<Target = [Block]>,
+    T: IntoIterator<Item = &'a Block>,
 {
     fn to_markup<F>(self) -> String
     where
-        F: Markup,
+        F: Markup + Default,
     {
-        <F>::markup(&self)
+        <F>::markup(self)
     }
-}
-
-mod private {
-    use super::Block;
 
-    pub trait Sealed {}
+    fn render<F>(self, formatter: &F) -> String
+    where
+        F: Markup,
+    {
+        formatter.render(self)
+    }
 
-    impl<T> Sealed for T where T: std::ops::Deref<Target = [Block]> {}
+    fn write_markup<F, W>(self, formatter: &F, writer: W) -> io::Result<()>
+    where
+        F: Markup,
+        W: io::Write,
+    {
+        formatter.write_markup(self, writer)
+    }
 }
@@ -1,10 +1,12 @@
-use crate::Content;
+use crate::{Content, Error};
 use http::uri::Uri;
+use std::fmt;
+use std::str::FromStr;
 
 /// A URI with optional label
 #[derive(Clone, Debug)]
 pub struct Link {
-    uri: Uri,
+    uri: UriRef,
     label: Option<Content>,
 }
 
@@ -19,16 +21,19 @@ impl Link {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn new(uri: Uri, label: Option<Content>) -> Self {
-        Link { uri, label }
+    pub fn new<U: Into<UriRef>>(uri: U, label: Option<Content>) -> Self {
+        Link {
+            uri: uri.into(),
+            label,
+        }
     }
 
     /// Returns a reference to the URI
-    pub fn uri(&self) -> &Uri {
+    pub fn uri(&self) -> &UriRef {
         &self.uri
     }
     /// Returns a mutable reference to the URI
-    pub fn uri_mut(&mut self) -> &mut Uri {
+    pub fn uri_mut(&mut self) -> &mut UriRef {
         &mut self.uri
     }
 
@@ -65,3 +70,199 @@ impl From<Uri> for Link {
         Link::new(uri, None)
     }
 }
+
+/// ```
+/// # fn main() -> mdiu::Result<()> {
+/// use mdiu::{Link, UriRef};
+/// let uri: UriRef = "posts/hello.gmi".parse()?;
+/// let link = Link::from(uri);
+/// # Ok(())
+/// # }
+/// ```
+impl From<UriRef> for Link {
+    fn from(uri: UriRef) -> Link {
+        Link::new(uri, None)
+    }
+}
+
+/// A URI reference, which is either a URI or a relative reference
+///
+/// Unlike [`Uri`], a `UriRef` holds any reference a Gemtext link can point to,
+/// including relative paths such as `posts/hello.gmi` or `../index.gmi`,
+/// and bare queries or fragments such as `#top`.
+/// The reference is kept as it was written, so it formats back unchanged.
+///
+/// Only the characters allowed by [RFC 3986] are accepted,
+/// so non-ASCII characters and spaces must be percent-encoded.
+///
+/// # Example
+/// ```
+/// # fn main() -> mdiu::Result<()> {
+/// use mdiu::UriRef;
+///
+/// let uri: UriRef = "../posts/hello.gmi?lang=en#comments".parse()?;
+///
+/// assert_eq!(uri.scheme(), None);
+/// assert_eq!(uri.path(), "../posts/hello.gmi");
+/// assert_eq!(uri.query(), Some("lang=en"));
+/// assert_eq!(uri.fragment(), Some("comments"));
+/// assert_eq!(uri, "../posts/hello.gmi?lang=en#comments");
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [RFC 3986]: https://www.rfc-editor.org/rfc/rfc3986
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct UriRef(String);
+
+impl UriRef {
+    /// Constructs a new, checked `UriRef`
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidUri`] if the input is empty
+    /// or contains characters that are not allowed in a URI.
+    pub fn new<T: AsRef<str>>(value: T) -> Result<Self, Error> {
+        value.as_ref().parse()
+    }
+
+    /// Returns the reference as a string slice
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the scheme, such as `gemini`, or `None` for a relative reference
+    pub fn scheme(&self) -> Option<&str> {
+        let end = self.0.find([':', '/', '?', '#'])?;
+        let scheme = &self.0[..end];
+        let valid = self.0[end..].starts_with(':')
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        valid.then_some(scheme)
+    }
+
+    /// Returns the authority, such as `example.org:1965`, if the reference has one
+    ///
+    /// A network-path reference such as `//example.org/` has an authority but no scheme.
+    pub fn authority(&self) -> Option<&str> {
+        let rest = self.hier_part().strip_prefix("//")?;
+        Some(&rest[..rest.find('/').unwrap_or(rest.len())])
+    }
+
+    /// Returns the path, which may be empty
+    pub fn path(&self) -> &str {
+        let hier_part = self.hier_part();
+        match hier_part.strip_prefix("//") {
+            Some(rest) => &rest[rest.find('/').unwrap_or(rest.len())..],
+            None => hier_part,
+        }
+    }
+
+    /// Returns the query, without the leading `?`
+    pub fn query(&self) -> Option<&str> {
+        let before_fragment = self.0.split('#').next().unwrap_or_default();
+        before_fragment.split_once('?').map(|(_, query)| query)
+    }
+
+    /// Returns the fragment, without the leading `#`
+    pub fn fragment(&self) -> Option<&str> {
+        self.0.split_once('#').map(|(_, fragment)| fragment)
+    }
+
+    /// Returns the part after the scheme and before any query or fragment
+    fn hier_part(&self) -> &str {
+        let start = self.scheme().map_or(0, |scheme| scheme.len() + 1);
+        let rest = &self.0[start..];
+        &rest[..rest.find(['?', '#']).unwrap_or(rest.len())]
+    }
+}
+
+impl FromStr for UriRef {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Unreserved and reserved characters, and `%` for percent-encoding
+        let allowed = |c: char| c.is_ascii_alphanumeric() || "-._~:/?#[]@!$&'()*+,;=%".contains(c);
+        if s.is_empty() || !s.chars().all(allowed) {
+            return Err(Error::InvalidUri);
+        }
+        Ok(UriRef(s.to_string()))
+    }
+}
+
+/// ```
+/// use http::uri::Uri;
+/// use mdiu::UriRef;
+///
+/// let uri = UriRef::from(Uri::from_static("gemini://example.org/"));
+/// assert_eq!(uri.scheme(), Some("gemini"));
+/// ```
+impl From<Uri> for UriRef {
+    fn from(uri: Uri) -> UriRef {
+        UriRef(uri.to_string())
+    }
+}
+
+impl AsRef<str> for UriRef {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for UriRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl PartialEq<str> for UriRef {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for UriRef {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn parts() {
+        let uri: UriRef = "GEMINI://host:1965/a/b.gmi?q#f".parse().unwrap();
+        assert_eq!(uri.scheme(), Some("GEMINI"));
+        assert_eq!(uri.authority(), Some("host:1965"));
+        assert_eq!(uri.path(), "/a/b.gmi");
+        assert_eq!(uri.query(), Some("q"));
+        assert_eq!(uri.fragment(), Some("f"));
+
+        let mailto: UriRef = "mailto:me@host".parse().unwrap();
+        assert_eq!(mailto.authority(), None);
+        assert_eq!(mailto.path(), "me@host");
+
+        let network: UriRef = "//host".parse().unwrap();
+        assert_eq!(
+            (network.scheme(), network.authority()),
+            (None, Some("host"))
+        );
+        assert_eq!(network.path(), "");
+
+        // A colon after a slash is part of the path
+        let relative: UriRef = "./a:b#".parse().unwrap();
+        assert_eq!(relative.scheme(), None);
+        assert_eq!(relative.path(), "./a:b");
+        assert_eq!(relative.fragment(), Some(""));
+    }
+
+    #[test]
+    fn errors() {
+        for invalid in ["", "a b", "/<script>", "/ñ", "a\tb"] {
+            assert_eq!(invalid.parse::<UriRef>(), Err(Error::InvalidUri));
+        }
+    }
+}
@@ -1,64 +1,366 @@
-use super::{Block, Level, Markup};
+use super::{Block, Level, LinkRewrite, LinkStyle, Markup};
+use std::fmt;
 
 /// A [Markdown 1.0.1] formatter, available with the `markdown` feature
 ///
-/// Links are formatted as lists of links.
+/// By default, links are formatted as lists of links.
+/// Text is escaped so that it renders literally.
+///
+/// Gemtext lines map to Markdown as follows, to keep the layout of the source:
+///
+/// * Adjacent text lines form one paragraph, separated by hard line breaks
+/// * Adjacent quote lines form one blockquote, separated by hard line breaks
+/// * An empty line ends a paragraph, like the blank line between other blocks
+/// * Each further empty line in a row adds an `&nbsp;` paragraph
+/// * Empty lines at the start and end of a document are dropped
+///
+/// # Example
+/// ```
+/// use mdiu::{LinkStyle, Markdown};
+///
+/// let markdown = Markdown::new().link_style(LinkStyle::Paragraph);
+/// ```
 ///
 /// [Markdown 1.0.1]: https://daringfireball.net/projects/markdown/
-pub struct Markdown;
+#[derive(Clone, Debug, Default)]
+pub struct Markdown {
+    link_style: LinkStyle,
+    code_style: CodeStyle,
+    rewrite: Option<LinkRewrite>,
+}
+
+/// How the [`Markdown`] formatter writes preformatted blocks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CodeStyle {
+    /// Indented code blocks, as in Markdown 1.0.1, which drop the alt text
+    #[default]
+    Indented,
+    /// Fenced code blocks, as in CommonMark, with the alt text as the info string
+    ///
+    /// ```
+    /// # fn main() -> mdiu::Result<()> {
+    /// use mdiu::{CodeStyle, Document, Markdown, ToMarkup};
+    ///
+    /// let markdown = Document::new()
+    ///     .preformatted_with_alt("fn main() {}", "rust")
+    ///     .build()?
+    ///     .render(&Markdown::new().code_style(CodeStyle::Fenced));
+    ///
+    /// assert_eq!(markdown, "```rust\nfn main() {}\n```\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    Fenced,
+}
+
+impl Markdown {
+    /// Constructs a new `Markdown` formatter with the default configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how links are laid out
+    pub fn link_style(mut self, link_style: LinkStyle) -> Self {
+        self.link_style = link_style;
+        self
+    }
+
+    /// Sets how preformatted blocks are written
+    pub fn code_style(mut self, code_style: CodeStyle) -> Self {
+        self.code_style = code_style;
+        self
+    }
+
+    /// Rewrites link URIs with a [`LinkRewrite`] policy
+    ///
+    /// Unlabeled links keep the original URI as their text.
+    /// Markdown cannot mark links as external.
+    pub fn rewrite(mut self, rewrite: LinkRewrite) -> Self {
+        self.rewrite = Some(rewrite);
+        self
+    }
+}
 
 impl Markup for Markdown {
-    fn markup(blocks: &[Block]) -> String {
-        let mut s = String::new();
+    fn fmt_markup<'a, I, W>(&self, blocks: I, w: &mut W) -> fmt::Result
+    where
+        I: IntoIterator<Item = &'a Block>,
+        W: fmt::Write + ?Sized,
+    {
+        let link_list = self.link_style == LinkStyle::List;
+        let mut blocks = blocks.into_iter().peekable();
+        // The last block that was not empty, and the number of empty blocks since
         let mut last_block: Option<&Block> = None;
+        let mut empty_blocks = 0;
 
-        for block in blocks {
-            match (last_block, block) {
-                (Some(Block::Link(_)), b) if !matches!(b, Block::Link(_)) => s += "\n",
-                (Some(Block::ListItem(_)), b) if !matches!(b, Block::ListItem(_)) => s += "\n",
-                _ => {}
+        while let Some(block) = blocks.next() {
+            if let Block::Empty = block {
+                empty_blocks += 1;
+                continue;
             }
 
-            let l = match block {
-                Block::Text(text) => format!("{}\n\n", text),
+            // Separate blocks with a blank line, except for lines of the same paragraph or list
+            if let Some(last_block) = last_block {
+                let adjacent = empty_blocks == 0
+                    && match (last_block, block) {
+                        (Block::Link(_), Block::Link(_)) => link_list,
+                        (Block::ListItem(_), Block::ListItem(_)) => true,
+                        (Block::Text(_), Block::Text(_)) => true,
+                        (Block::Quote(_), Block::Quote(_)) => true,
+                        _ => false,
+                    };
+                if !adjacent {
+                    writeln!(w)?;
+                    // The first empty block is the blank line itself
+                    for _ in 1..empty_blocks {
+                        w.write_str("&nbsp;\n\n")?;
+                    }
+                }
+            }
+            empty_blocks = 0;
+
+            match block {
+                Block::Text(text) => {
+                    // Trailing spaces make a hard line break before the next text line
+                    let hard_break = matches!(blocks.peek(), Some(Block::Text(_)));
+                    writeln!(w, "{}{}", escape(text), if hard_break { "  " } else { "" })?
+                }
                 Block::Link(link) => {
-                    match link.label() {
-                        Some(label) => {
-                            format!("* [{}]({})\n", label, link.uri())
+                    let uri = link.uri().to_string();
+                    let href = match &self.rewrite {
+                        Some(rewrite) => rewrite.uri(link.uri()).to_string(),
+                        None => uri.clone(),
+                    };
+                    let bullet = if link_list { "* " } else { "" };
+                    // Markdown 1.0.1 autolink syntax doesn't work for relative URIs,
+                    // so unlabeled links use the URI as their text
+                    // https://daringfireball.net/projects/markdown/syntax#autolink
+                    let text = link.label().as_ref().map_or(uri.as_str(), |l| l.as_ref());
+                    writeln!(w, "{}[{}]({})", bullet, escape(text), escape_uri(&href))?
+                }
+                Block::Heading(Level::One, text) => writeln!(w, "# {}", escape(text))?,
+                Block::Heading(Level::Two, text) => writeln!(w, "## {}", escape(text))?,
+                Block::Heading(Level::Three, text) => writeln!(w, "### {}", escape(text))?,
+                Block::ListItem(text) => writeln!(w, "* {}", escape(text))?,
+                Block::Quote(text) => {
+                    let hard_break = matches!(blocks.peek(), Some(Block::Quote(_)));
+                    writeln!(
+                        w,
+                        "> {}{}",
+                        escape(text),
+                        if hard_break { "  " } else { "" }
+                    )?
+                }
+                // Code blocks are literal and need no escaping
+                Block::Preformatted(pre) => match self.code_style {
+                    CodeStyle::Indented => {
+                        for line in pre.text().lines() {
+                            writeln!(w, "    {}", line)?;
                         }
-                        None => {
-                            // Markdown 1.0.1 autolink syntax doesn't work for relative URIs
-                            // https://daringfireball.net/projects/markdown/syntax#autolink
-                            format!("* [{0}]({0})\n", link.uri())
+                    }
+                    CodeStyle::Fenced => {
+                        let alt = pre.alt().as_ref().map_or("", |alt| alt.as_ref());
+                        // Backticks cannot appear in the info string of a backtick fence
+                        let c = if alt.contains('`') { '~' } else { '`' };
+                        let fence = c.to_string().repeat(fence_length(pre.text(), c));
+                        writeln!(w, "{}{}", fence, alt)?;
+                        for line in pre.text().lines() {
+                            writeln!(w, "{}", line)?;
                         }
+                        writeln!(w, "{}", fence)?;
                     }
-                }
-                Block::Heading(Level::One, text) => format!("# {}\n\n", text),
-                Block::Heading(Level::Two, text) => format!("## {}\n\n", text),
-                Block::Heading(Level::Three, text) => format!("### {}\n\n", text),
-                Block::ListItem(text) => format!("* {}\n", text),
-                Block::Quote(text) => format!("> {}\n\n", text),
-                Block::Preformatted(pre) => {
-                    let lines: String = pre
-                        .text()
-                        .lines()
-                        .map(|line| format!("    {}", line))
-                        .collect();
-                    format!("{}\n\n", lines)
-                }
-                // TODO
-                Block::Empty => "".to_string(),
-            };
+                },
+                Block::Empty => {}
+            }
 
             last_block = Some(block);
-            s += &l;
         }
 
-        // Remove trailing newline
-        if s.ends_with("\n\n") {
-            s.pop();
+        Ok(())
+    }
+}
+
+/// Escapes a line of text so Markdown renders it literally
+fn escape<T: AsRef<str> + ?Sized>(text: &T) -> Escape<'_> {
+    Escape(text.as_ref())
+}
+
+struct Escape<'a>(&'a str);
+
+impl fmt::Display for Escape<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = self.0;
+        let indent = text.len() - text.trim_start_matches([' ', '\t']).len();
+        let (indent, mut rest) = text.split_at(indent);
+        // Indentation would start a code block, and is otherwise dropped
+        for c in indent.chars() {
+            write!(f, "&#{};", c as u32)?;
         }
 
-        s
+        // Characters that start a block only at the start of a line
+        if let Some(c @ ('>' | '+' | '-')) = rest.chars().next() {
+            write!(f, "\\{}", c)?;
+            rest = &rest[1..];
+        } else if let Some(after) = rest.strip_prefix('=') {
+            // `=` cannot be backslash-escaped, and would underline a heading
+            f.write_str("&#61;")?;
+            rest = after;
+        } else {
+            let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            if digits > 0 && rest[digits..].starts_with('.') {
+                write!(f, "{}\\.", &rest[..digits])?;
+                rest = &rest[digits + 1..];
+            }
+        }
+
+        while let Some(i) = rest.find(['\\', '`', '*', '_', '[', ']', '#', '&', '<']) {
+            f.write_str(&rest[..i])?;
+            match rest.as_bytes()[i] {
+                b'&' => f.write_str("&amp;")?,
+                b'<' => f.write_str("&lt;")?,
+                c => write!(f, "\\{}", c as char)?,
+            }
+            rest = &rest[i + 1..];
+        }
+        f.write_str(rest)
+    }
+}
+
+/// Returns a fence length longer than any run of `c` in the text
+fn fence_length(text: &str, c: char) -> usize {
+    let longest = text
+        .split(|other| other != c)
+        .map(|run| run.len())
+        .max()
+        .unwrap_or(0);
+    longest.max(2) + 1
+}
+
+/// Percent-encodes characters that would end a link destination
+fn escape_uri(uri: &str) -> String {
+    uri.replace('(', "%28").replace(')', "%29")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use http::uri::Uri;
+
+    #[test]
+    fn link_paragraphs() {
+        let markdown = Document::new()
+            .link(Uri::from_static("/a"))
+            .link_with_label(Uri::from_static("/b"), "b")
+            .build()
+            .unwrap()
+            .render(&Markdown::new().link_style(LinkStyle::Paragraph));
+
+        assert_eq!(markdown, "[/a](/a)\n\n[b](/b)\n");
+    }
+
+    #[test]
+    fn rewrite() {
+        let markdown = Document::new()
+            .link(Uri::from_static("/a.gmi"))
+            .link_with_label(Uri::from_static("gemini://host/b"), "b")
+            .build()
+            .unwrap()
+            .render(&Markdown::new().rewrite(
+                LinkRewrite::new().gemini(GeminiLinks::Proxy(Uri::from_static("https://proxy/g"))),
+            ));
+
+        assert_eq!(
+            markdown,
+            "* [/a.gmi](/a.html)\n* [b](https://proxy/g/host/b)\n"
+        );
+    }
+
+    #[test]
+    fn empty_lines() {
+        let markdown = Document::new()
+            .empty()
+            .text("one")
+            .text("two")
+            .empty()
+            .text("three")
+            .empty()
+            .empty()
+            .empty()
+            .list_item("item")
+            .empty()
+            .quote("four")
+            .quote("five")
+            .build()
+            .unwrap()
+            .to_markup::<Markdown>();
+
+        assert_eq!(
+            markdown,
+            "one  \ntwo\n\nthree\n\n&nbsp;\n\n&nbsp;\n\n* item\n\n> four  \n> five\n"
+        );
+    }
+
+    #[test]
+    fn fences() {
+        let markdown = Document::new()
+            .preformatted("x ```\n~~~")
+            .preformatted_with_alt("~~~~", "a`b")
+            .build()
+            .unwrap()
+            .render(&Markdown::new().code_style(CodeStyle::Fenced));
+
+        assert_eq!(
+            markdown,
+            "````\nx ```\n~~~\n````\n\n~~~~~a`b\n~~~~\n~~~~~\n"
+        );
+    }
+
+    #[test]
+    fn escaping() {
+        let markdown = Document::new()
+            .h1("C# is *fun* #")
+            .text("1. not a list")
+            .empty()
+            .text("  - not a list either")
+            .empty()
+            .text("    not code")
+            .empty()
+            .text("\tnor this")
+            .empty()
+            .text("snake_case [not](a link) `code` <b> &amp; \\")
+            .empty()
+            .text("=== not a heading")
+            .link_with_label(Uri::from_static("/wiki/Foo_(bar)"), "[a] label")
+            .quote("> nested")
+            .list_item("+ nested")
+            .preformatted("*literal*\n_code_")
+            .build()
+            .unwrap()
+            .to_markup::<Markdown>();
+
+        let expected = r#"# C\# is \*fun\* \#
+
+1\. not a list
+
+&#32;&#32;\- not a list either
+
+&#32;&#32;&#32;&#32;not code
+
+&#9;nor this
+
+snake\_case \[not\](a link) \`code\` &lt;b> &amp;amp; \\
+
+&#61;== not a heading
+
+* [\[a\] label](/wiki/Foo_%28bar%29)
+
+> \> nested
+
+* \+ nested
+
+    *literal*
+    _code_
+"#;
+        assert_eq!(markdown, expected);
     }
 }
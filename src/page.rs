@@ -0,0 +1,236 @@
+use super::{html::escape, Block, Html, Level, Markup};
+use std::fmt;
+
+/// A standalone HTML page formatter, available with the `html` feature
+///
+/// Wraps the output of [`Html`] in a complete HTML document with
+/// a character set, viewport, title, optional language and a stylesheet.
+/// Unless set, the title is taken from the first [`Level::One`] heading.
+///
+/// # Example
+/// ```
+/// # fn main() -> mdiu::Result<()> {
+/// use mdiu::{Document, HtmlPage, Stylesheet, ToMarkup};
+///
+/// let page = Document::new()
+///     .h1("my gemlog")
+///     .build()?
+///     .render(&HtmlPage::new().lang("en").stylesheet(Stylesheet::None));
+///
+/// assert_eq!(
+///     page,
+///     r#"<!DOCTYPE html>
+/// <html lang="en">
+/// <head>
+/// <meta charset="utf-8">
+/// <meta name="viewport" content="width=device-width, initial-scale=1">
+/// <title>my gemlog</title>
+/// </head>
+/// <body>
+/// <h1>my gemlog</h1>
+/// </body>
+/// </html>
+/// "#
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct HtmlPage {
+    html: Html,
+    title: Option<String>,
+    lang: Option<String>,
+    stylesheet: Stylesheet,
+}
+
+/// The stylesheet of an [`HtmlPage`]
+#[derive(Clone, Debug, Default)]
+pub enum Stylesheet {
+    /// A small built-in stylesheet that resembles a Gemini client
+    #[default]
+    Default,
+    /// CSS included in a `<style>` element
+    Inline(String),
+    /// A URL linked with a `<link rel="stylesheet">` element
+    Link(String),
+    /// No stylesheet
+    None,
+}
+
+/// The stylesheet used by [`Stylesheet::Default`]
+const DEFAULT_STYLESHEET: &str = "body {
+  max-width: 38em;
+  margin: 0 auto;
+  padding: 1em;
+  font-family: sans-serif;
+  line-height: 1.5;
+  color: #222;
+  background: #fff;
+}
+h1, h2, h3 {
+  line-height: 1.2;
+}
+p {
+  margin: 0;
+}
+a {
+  color: #0645ad;
+}
+ul {
+  padding-left: 1.5em;
+}
+ul:has(> li > a) {
+  list-style: none;
+  padding-left: 0;
+}
+li > a::before {
+  content: \"\u{21d2} \";
+}
+blockquote {
+  margin: 0.5em 0;
+  padding-left: 1em;
+  border-left: 3px solid #ccc;
+  font-style: italic;
+}
+pre {
+  overflow-x: auto;
+  padding: 0.5em;
+  background: #f4f4f4;
+}
+.hl-keyword {
+  color: #a626a4;
+}
+.hl-string {
+  color: #50a14f;
+}
+.hl-comment {
+  color: #888;
+  font-style: italic;
+}
+.hl-number {
+  color: #986801;
+}
+";
+
+impl HtmlPage {
+    /// Constructs a new `HtmlPage` formatter with the default configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the [`Html`] formatter used for the body
+    pub fn html(mut self, html: Html) -> Self {
+        self.html = html;
+        self
+    }
+
+    /// Sets the title, instead of using the first [`Level::One`] heading
+    pub fn title<T: Into<String>>(mut self, title: T) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Sets the language of the page, such as `en`
+    pub fn lang<T: Into<String>>(mut self, lang: T) -> Self {
+        self.lang = Some(lang.into());
+        self
+    }
+
+    /// Sets the stylesheet
+    pub fn stylesheet(mut self, stylesheet: Stylesheet) -> Self {
+        self.stylesheet = stylesheet;
+        self
+    }
+}
+
+impl Markup for HtmlPage {
+    fn fmt_markup<'a, I, W>(&self, blocks: I, w: &mut W) -> fmt::Result
+    where
+        I: IntoIterator<Item = &'a Block>,
+        W: fmt::Write + ?Sized,
+    {
+        // The title may come from any block, so hold on to the blocks before writing the head
+        let blocks: Vec<&Block> = blocks.into_iter().collect();
+        let title = self.title.as_deref().or_else(|| {
+            blocks.iter().find_map(|block| match block {
+                Block::Heading(Level::One, text) => Some(text.as_ref()),
+                _ => None,
+            })
+        });
+
+        w.write_str("<!DOCTYPE html>\n")?;
+        match &self.lang {
+            Some(lang) => writeln!(w, "<html lang=\"{}\">", escape(lang))?,
+            None => w.write_str("<html>\n")?,
+        }
+        w.write_str("<head>\n<meta charset=\"utf-8\">\n")?;
+        w.write_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n")?;
+        writeln!(w, "<title>{}</title>", escape(title.unwrap_or_default()))?;
+        match &self.stylesheet {
+            Stylesheet::Default => write!(w, "<style>\n{}</style>\n", DEFAULT_STYLESHEET)?,
+            Stylesheet::Inline(css) => write!(w, "<style>\n{}\n</style>\n", escape_style(css))?,
+            Stylesheet::Link(href) => {
+                writeln!(w, "<link rel=\"stylesheet\" href=\"{}\">", escape(href))?
+            }
+            Stylesheet::None => {}
+        }
+        w.write_str("</head>\n<body>\n")?;
+        self.html.fmt_markup(blocks, w)?;
+        w.write_str("</body>\n</html>\n")
+    }
+}
+
+/// Escapes CSS for a `<style>` element
+///
+/// Only `</style`, matched case-insensitively, can end the element early.
+fn escape_style(css: &str) -> String {
+    let mut escaped = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(i) = rest.find("</") {
+        let tag = rest.as_bytes().get(i + 2..i + 7);
+        escaped.push_str(&rest[..i]);
+        if tag.is_some_and(|tag| tag.eq_ignore_ascii_case(b"style")) {
+            escaped.push_str("<\\/");
+        } else {
+            escaped.push_str("</");
+        }
+        rest = &rest[i + 2..];
+    }
+    escaped.push_str(rest);
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn head() {
+        let page = Document::new().text("no heading").build().unwrap().render(
+            &HtmlPage::new()
+                .title("<my gemlog>")
+                .stylesheet(Stylesheet::Link("/style.css".to_string())),
+        );
+
+        assert!(page.starts_with("<!DOCTYPE html>\n<html>\n"));
+        assert!(page.contains("<title>&lt;my gemlog&gt;</title>\n"));
+        assert!(page.contains("<link rel=\"stylesheet\" href=\"/style.css\">\n</head>\n"));
+        assert!(page.ends_with("<body>\n<p>no heading</p>\n</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn default_stylesheet() {
+        let page = Vec::new().render(&HtmlPage::new());
+
+        assert!(page.contains("<title></title>\n<style>\nbody {"));
+    }
+
+    #[test]
+    fn inline_stylesheet() {
+        let css = "p {}</STYLE><script>alert(1)</script></Style </p";
+        let page = Vec::new().render(&HtmlPage::new().stylesheet(Stylesheet::Inline(css.into())));
+
+        assert!(page
+            .contains("<style>\np {}<\\/STYLE><script>alert(1)</script><\\/Style </p\n</style>\n"));
+    }
+}
@@ -0,0 +1,552 @@
+use crate::{Block, Content, Error, Level, Link, Preformatted, Result, UriRef};
+use std::io::BufRead;
+use std::ops::Range;
+
+/// Parses Gemtext into [`Block`]s, available with the `parsing` feature
+///
+/// Parsed blocks format back to the same Gemtext with [`Gemtext`],
+/// as long as the input uses `\n` line endings, ends with a newline
+/// and uses a single space after line-type prefixes.
+/// A preformatted block with no lines, such as ```` ```\n```\n ````,
+/// has the same empty text as a block with one empty line,
+/// and formats back with one empty line.
+///
+/// Lines with a line-type prefix but nothing after it, such as a lone `>`,
+/// cannot be represented with [`Content`] and are kept as [`Block::Text`],
+/// which [`Document::strict`] validation rejects.
+///
+/// Non-ASCII characters in link URIs are percent-encoded,
+/// so `=> gemini://example.org/ñ` formats back as `=> gemini://example.org/%C3%B1`.
+///
+/// Link URIs are kept as [`UriRef`]s, so relative references such as `posts/foo.gmi`,
+/// `../up.gmi` or `#top`, and fragments, format back unchanged.
+///
+/// # Errors
+/// Returns an [`Error::Parse`] with the line and column
+/// if a link URI contains characters that are not allowed in a URI, such as `<`,
+/// or a line contains a carriage return.
+///
+/// To parse large documents without reading them into a `String` first, use [`Parser`].
+/// To keep the source location of each block, use [`parse_spanned`].
+///
+/// # Example
+/// ```
+/// # fn main() -> mdiu::Result<()> {
+/// use mdiu::{Gemtext, ToMarkup};
+///
+/// let gemtext = "# my gemlog\n=> /posts recent posts\n";
+/// let blocks = mdiu::parse(gemtext)?;
+///
+/// assert_eq!(blocks.to_markup::<Gemtext>(), gemtext);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Gemtext`]: crate::Gemtext
+/// [`Document::strict`]: crate::Document::strict
+pub fn parse(s: &str) -> Result<Vec<Block>> {
+    Parser::new(s.as_bytes()).collect()
+}
+
+/// A streaming Gemtext parser, available with the `parsing` feature
+///
+/// Reads one line at a time from a [`BufRead`] and yields [`Block`]s as they are completed,
+/// so only the current line (or the current preformatted block) is held in memory.
+/// See [`parse`] for details on how lines are parsed.
+///
+/// An IO error, including invalid UTF-8, is returned as an [`Error::Parse`]
+/// with the line that could not be read and column 1.
+/// After an IO error the parser yields no more blocks.
+///
+/// # Example
+/// ```
+/// # fn main() -> mdiu::Result<()> {
+/// use mdiu::{Block, Parser};
+///
+/// let reader = "# my gemlog\n```\n@_@\n```\n".as_bytes();
+/// let blocks = Parser::new(reader).collect::<mdiu::Result<Vec<Block>>>()?;
+///
+/// assert_eq!(blocks.len(), 2);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Parser<R> {
+    reader: R,
+    buf: String,
+    pre: Option<Pre>,
+    offset: usize,
+    line: usize,
+    done: bool,
+}
+
+/// An open preformatted block
+struct Pre {
+    alt: Option<Content>,
+    text: String,
+    span: Span,
+    body: Span,
+    lines: usize,
+}
+
+impl Pre {
+    fn push(&mut self, line: &str, span: Span) {
+        if self.lines == 0 {
+            self.body = span;
+        } else {
+            self.text.push('\n');
+            self.body.end = span.end;
+            self.body.end_line = span.end_line;
+        }
+        self.text.push_str(line);
+        self.lines += 1;
+        self.span.end = span.end;
+        self.span.end_line = span.end_line;
+    }
+
+    fn close(mut self, fence: Span) -> SpannedBlock {
+        self.span.end = fence.end;
+        self.span.end_line = fence.end_line;
+        self.into_block()
+    }
+
+    fn into_block(self) -> SpannedBlock {
+        SpannedBlock {
+            block: Block::Preformatted(Preformatted::new(self.text, self.alt)),
+            span: self.span,
+            inner: Some(self.body),
+        }
+    }
+}
+
+impl<R: BufRead> Parser<R> {
+    /// Constructs a new `Parser` reading from `reader`
+    pub fn new(reader: R) -> Self {
+        Parser {
+            reader,
+            buf: String::new(),
+            pre: None,
+            offset: 0,
+            line: 0,
+            done: false,
+        }
+    }
+
+    /// Converts the parser into an iterator of [`SpannedBlock`]s
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> mdiu::Result<()> {
+    /// use mdiu::Parser;
+    ///
+    /// let mut parser = Parser::new("# my gemlog\n=> /posts\n".as_bytes()).spanned();
+    /// let link = parser.nth(1).unwrap()?;
+    ///
+    /// assert_eq!(link.span().start_line(), 2);
+    /// assert_eq!(link.uri_span().map(|span| span.bytes()), Some(15..21));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn spanned(self) -> Spanned<R> {
+        Spanned(self)
+    }
+
+    fn next_spanned(&mut self) -> Option<Result<SpannedBlock>> {
+        while !self.done {
+            self.buf.clear();
+            let read = match self.reader.read_line(&mut self.buf) {
+                Ok(0) => {
+                    self.done = true;
+                    // An unclosed preformatted block ends with the document
+                    return self.pre.take().map(|pre| Ok(pre.into_block()));
+                }
+                Ok(read) => read,
+                Err(e) => {
+                    self.done = true;
+                    // The line cannot be read, so the column is unknown
+                    return Some(Err(Error::Parse {
+                        line: self.line + 1,
+                        column: 1,
+                        error: Box::new(e.into()),
+                    }));
+                }
+            };
+
+            let line = trim_newline(&self.buf);
+            self.line += 1;
+            let span = Span {
+                start: self.offset,
+                end: self.offset + line.len(),
+                start_line: self.line,
+                end_line: self.line,
+            };
+            self.offset += read;
+
+            match (self.pre.as_mut(), line.strip_prefix("```")) {
+                (None, Some(alt)) => match content(line, alt) {
+                    Ok(alt) => {
+                        self.pre = Some(Pre {
+                            alt,
+                            text: String::new(),
+                            span,
+                            // Empty until the first line of the body is pushed
+                            body: Span {
+                                start: self.offset,
+                                end: self.offset,
+                                start_line: self.line + 1,
+                                end_line: self.line + 1,
+                            },
+                            lines: 0,
+                        })
+                    }
+                    Err(e) => return Some(Err(locate(self.line, line, e))),
+                },
+                (Some(_), Some(_)) => return self.pre.take().map(|pre| Ok(pre.close(span))),
+                (Some(pre), None) => pre.push(line, span),
+                (None, None) => {
+                    let block = parse_line(line)
+                        .map(|(block, uri)| SpannedBlock {
+                            block,
+                            inner: uri.map(|uri| Span {
+                                start: span.start + uri.start,
+                                end: span.start + uri.end,
+                                ..span
+                            }),
+                            span,
+                        })
+                        .map_err(|e| locate(self.line, line, e));
+                    return Some(block);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl<R: BufRead> Iterator for Parser<R> {
+    type Item = Result<Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_spanned()
+            .map(|result| result.map(SpannedBlock::into_block))
+    }
+}
+
+/// An iterator of [`SpannedBlock`]s, available with the `parsing` feature
+///
+/// Created with [`Parser::spanned`].
+pub struct Spanned<R>(Parser<R>);
+
+impl<R: BufRead> Iterator for Spanned<R> {
+    type Item = Result<SpannedBlock>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_spanned()
+    }
+}
+
+/// Parses Gemtext into [`SpannedBlock`]s, available with the `parsing` feature
+///
+/// # Errors
+/// See [`parse`].
+pub fn parse_spanned(s: &str) -> Result<Vec<SpannedBlock>> {
+    Parser::new(s.as_bytes()).spanned().collect()
+}
+
+/// A region of Gemtext source
+///
+/// Byte offsets exclude line endings and lines are counted from 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    start: usize,
+    end: usize,
+    start_line: usize,
+    end_line: usize,
+}
+
+impl Span {
+    /// Returns the byte range
+    pub fn bytes(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Returns the first line
+    pub fn start_line(&self) -> usize {
+        self.start_line
+    }
+
+    /// Returns the last line
+    pub fn end_line(&self) -> usize {
+        self.end_line
+    }
+
+    /// Returns `true` if the span covers no bytes
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// A parsed [`Block`] with its source [`Span`]s
+#[derive(Clone, Debug)]
+pub struct SpannedBlock {
+    block: Block,
+    span: Span,
+    // The span of a link URI or a preformatted body
+    inner: Option<Span>,
+}
+
+impl SpannedBlock {
+    /// Returns a reference to the block
+    pub fn block(&self) -> &Block {
+        &self.block
+    }
+
+    /// Returns the span of the whole block
+    ///
+    /// A preformatted block spans from its opening to its closing line.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Returns the span of the URI if the block is a [`Block::Link`]
+    pub fn uri_span(&self) -> Option<Span> {
+        match self.block {
+            Block::Link(_) => self.inner,
+            _ => None,
+        }
+    }
+
+    /// Returns the span of the text if the block is a [`Block::Preformatted`]
+    ///
+    /// An empty body has an empty span on the line after the opening line.
+    pub fn body_span(&self) -> Option<Span> {
+        match self.block {
+            Block::Preformatted(_) => self.inner,
+            _ => None,
+        }
+    }
+
+    /// Consumes the `SpannedBlock`, returning the block
+    pub fn into_block(self) -> Block {
+        self.block
+    }
+}
+
+/// Removes a trailing `\n` or `\r\n`
+fn trim_newline(line: &str) -> &str {
+    match line.strip_suffix('\n') {
+        Some(line) => line.strip_suffix('\r').unwrap_or(line),
+        None => line,
+    }
+}
+
+/// An error at a byte offset within a line
+type LineError = (usize, Error);
+
+/// Converts a [`LineError`] into an [`Error::Parse`]
+fn locate(line_number: usize, line: &str, (offset, error): LineError) -> Error {
+    Error::Parse {
+        line: line_number,
+        column: line[..offset].chars().count() + 1,
+        error: Box::new(error),
+    }
+}
+
+/// Parses a line outside of a preformatted block, returning the block and the URI range of a link
+fn parse_line(line: &str) -> std::result::Result<(Block, Option<Range<usize>>), LineError> {
+    if line.is_empty() {
+        return Ok((Block::Empty, None));
+    }
+
+    if let Some(rest) = line.strip_prefix("=>") {
+        if let Some((link, uri)) = parse_link(line, rest)? {
+            return Ok((link, Some(uri)));
+        }
+    }
+
+    let block = if line.starts_with("=>") {
+        None
+    } else if let Some(rest) = line.strip_prefix("###") {
+        content(line, rest.trim_start())?.map(|c| Block::Heading(Level::Three, c))
+    } else if let Some(rest) = line.strip_prefix("##") {
+        content(line, rest.trim_start())?.map(|c| Block::Heading(Level::Two, c))
+    } else if let Some(rest) = line.strip_prefix('#') {
+        content(line, rest.trim_start())?.map(|c| Block::Heading(Level::One, c))
+    } else if let Some(rest) = line.strip_prefix("* ") {
+        content(line, rest)?.map(Block::ListItem)
+    } else if let Some(rest) = line.strip_prefix('>') {
+        content(line, rest.trim_start())?.map(Block::Quote)
+    } else {
+        None
+    };
+
+    match block {
+        Some(block) => Ok((block, None)),
+        None => Ok((Block::Text(content(line, line)?.unwrap()), None)),
+    }
+}
+
+/// Parses the remainder of a link line, returning `None` if there is no URI
+fn parse_link(
+    line: &str,
+    rest: &str,
+) -> std::result::Result<Option<(Block, Range<usize>)>, LineError> {
+    let rest = rest.trim_start();
+    if rest.is_empty() {
+        return Ok(None);
+    }
+
+    let (uri, label) = match rest.split_once(char::is_whitespace) {
+        Some((uri, label)) => (uri, content(line, label.trim_start())?),
+        None => (rest, None),
+    };
+    let start = line.len() - rest.len();
+    let range = start..start + uri.len();
+    let uri: UriRef = percent_encode(uri)
+        .parse()
+        .map_err(|_| (start, Error::InvalidUri))?;
+
+    Ok(Some((Block::Link(Link::new(uri, label)), range)))
+}
+
+/// Percent-encodes the non-ASCII characters of a URI
+fn percent_encode(uri: &str) -> String {
+    let mut encoded = String::with_capacity(uri.len());
+    for c in uri.chars() {
+        if c.is_ascii() {
+            encoded.push(c);
+        } else {
+            let mut buf = [0; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+    encoded
+}
+
+/// Parses optional `Content` from the end of a line, returning `None` if the text is empty
+fn content(line: &str, text: &str) -> std::result::Result<Option<Content>, LineError> {
+    if text.is_empty() {
+        return Ok(None);
+    }
+
+    text.parse().map(Some).map_err(|e| {
+        let start = line.len() - text.len();
+        (start + text.find(['\n', '\r']).unwrap_or(0), e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn line_types() {
+        let blocks = parse("=>  /a  label\n##x\n* \n>\n```alt\n* a\n\n```\n").unwrap();
+
+        assert!(matches!(&blocks[0], Block::Link(link)
+            if link.uri() == "/a" && link.label().as_ref().unwrap().as_ref() == "label"));
+        assert!(matches!(&blocks[1], Block::Heading(Level::Two, c) if c.as_ref() == "x"));
+        assert!(matches!(&blocks[2], Block::Text(c) if c.as_ref() == "* "));
+        assert!(matches!(&blocks[3], Block::Text(c) if c.as_ref() == ">"));
+        assert!(matches!(&blocks[4], Block::Preformatted(pre)
+            if pre.text() == "* a\n" && pre.alt().as_ref().unwrap().as_ref() == "alt"));
+        assert_eq!(blocks.len(), 5);
+    }
+
+    #[test]
+    fn unclosed_preformatted() {
+        let blocks = parse("```\n@_@").unwrap();
+
+        assert!(matches!(&blocks[0], Block::Preformatted(pre)
+            if pre.text() == "@_@" && pre.alt().is_none()));
+    }
+
+    #[test]
+    fn empty_preformatted() {
+        for gemtext in ["```\n```\n", "```\n\n```\n"] {
+            let blocks = parse(gemtext).unwrap();
+            assert!(matches!(&blocks[0], Block::Preformatted(pre) if pre.text().is_empty()));
+            assert_eq!(blocks.to_markup::<Gemtext>(), "```\n\n```\n");
+        }
+    }
+
+    #[test]
+    fn streaming() {
+        let mut parser = Parser::new("text\r\n```\r\n@_@\r\n".as_bytes());
+
+        assert!(matches!(parser.next(), Some(Ok(Block::Text(c))) if c.as_ref() == "text"));
+        assert!(matches!(parser.next(), Some(Ok(Block::Preformatted(pre))) if pre.text() == "@_@"));
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn spans() {
+        let blocks = parse_spanned("=> /a\r\n```\r\n1\r\n2\r\n```\r\n```\n").unwrap();
+
+        assert_eq!(blocks[0].span().bytes(), 0..5);
+        assert_eq!(blocks[0].uri_span().map(|span| span.bytes()), Some(3..5));
+        assert_eq!(blocks[1].span().bytes(), 7..21);
+        assert_eq!(
+            (blocks[1].span().start_line(), blocks[1].span().end_line()),
+            (2, 5)
+        );
+        assert_eq!(blocks[1].body_span().map(|span| span.bytes()), Some(12..16));
+        assert_eq!(
+            blocks[1]
+                .body_span()
+                .map(|span| (span.start_line(), span.end_line())),
+            Some((3, 4))
+        );
+        assert_eq!(blocks[2].span().bytes(), 23..26);
+        assert!(blocks[2].body_span().unwrap().is_empty());
+    }
+
+    #[test]
+    fn link_uris() {
+        let blocks = parse("=> gemini://host/ñ x\n").unwrap();
+        assert!(matches!(&blocks[0], Block::Link(link) if link.uri() == "gemini://host/%C3%B1"));
+
+        let gemtext = "=> posts/foo.gmi label\n\
+                       => ../up.gmi\n\
+                       => ./a.gmi\n\
+                       => ?q\n\
+                       => #top\n\
+                       => //host/a.gmi\n\
+                       => gemini://h/p?q#f\n";
+        assert_eq!(parse(gemtext).unwrap().to_markup::<Gemtext>(), gemtext);
+
+        let blocks = parse_spanned("=> gemini://h/p?q#f x\n").unwrap();
+        assert_eq!(blocks[0].uri_span().map(|span| span.bytes()), Some(3..19));
+        assert!(matches!(blocks[0].block(), Block::Link(link)
+            if link.uri().fragment() == Some("f")));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse("text\n=>  /<script> label").err(),
+            Some(Error::Parse {
+                line: 2,
+                column: 5,
+                error: Box::new(Error::InvalidUri)
+            })
+        );
+        assert_eq!(
+            parse("## é\rmore").err(),
+            Some(Error::Parse {
+                line: 1,
+                column: 5,
+                error: Box::new(Error::InvalidContent)
+            })
+        );
+
+        let invalid_utf8: &[u8] = b"ok\nfine\n# bad \xff\n";
+        let error = Parser::new(invalid_utf8).nth(2).unwrap().unwrap_err();
+        assert!(matches!(&error, Error::Parse { line: 3, column: 1, error }
+            if matches!(error.as_ref(), Error::Io(e) if e.kind() == std::io::ErrorKind::InvalidData)));
+        let source = std::error::Error::source(&error).and_then(std::error::Error::source);
+        assert!(source.is_some_and(|source| source.is::<std::io::Error>()));
+    }
+}
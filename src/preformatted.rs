@@ -1,4 +1,4 @@
-use crate::Content;
+use crate::{Content, Error};
 
 /// Preformatted text with optional alt text
 #[derive(Clone, Debug)]
@@ -41,4 +41,38 @@ impl Preformatted {
     pub fn alt_mut(&mut self) -> &mut Option<Content> {
         &mut self.alt
     }
+
+    /// Validates `Preformatted`
+    ///
+    /// # Errors
+    /// If a line of the text starts with ` ``` `, which would end the block early,
+    /// or the alt text is invalid, an [`Error`] will be returned.
+    ///
+    /// # Example
+    /// ```
+    /// # use mdiu::*;
+    /// let pre = Preformatted::new("```\nrm -rf /", None);
+    /// assert_eq!(Err(Error::InvalidPreformatted), pre.validate());
+    /// ```
+    pub fn validate(&self) -> crate::Result<()> {
+        if self.text.lines().any(|line| line.starts_with("```")) {
+            return Err(Error::InvalidPreformatted);
+        }
+
+        self.alt.as_ref().map_or(Ok(()), |alt| alt.validate())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn errors() {
+        let pre = Preformatted::new("fn main() {}\n```\n", None);
+        assert_eq!(pre.validate(), Err(Error::InvalidPreformatted));
+
+        let pre = Preformatted::new("a ```", None);
+        assert_eq!(pre.validate(), Ok(()));
+    }
 }
@@ -0,0 +1,215 @@
+use crate::{Block, Link, UriRef};
+use http::uri::Uri;
+
+/// A policy for rewriting link URIs when publishing Gemtext to the web
+///
+/// By default, relative links to `.gmi` files are rewritten to `.html`
+/// and all other links are kept, including `gemini://` and `mailto:` links.
+/// Network-path references such as `//example.org/` are resolved
+/// against a Gemini document, so they are treated as `gemini://` links.
+///
+/// The policy can be set on the `Html` and `Markdown` formatters,
+/// or applied to blocks before formatting with [`apply`].
+///
+/// # Example
+/// ```
+/// # fn main() -> mdiu::Result<()> {
+/// use http::uri::Uri;
+/// use mdiu::{Document, Gemtext, GeminiLinks, LinkRewrite, ToMarkup};
+///
+/// let mut doc = Document::new()
+///     .link(Uri::from_static("/posts/hello.gmi"))
+///     .link(Uri::from_static("gemini://example.org/"))
+///     .link(Uri::from_static("mailto:me@example.org"))
+///     .build()?;
+///
+/// let rewrite = LinkRewrite::new().gemini(GeminiLinks::Proxy(Uri::from_static(
+///     "https://portal.example/gemini/",
+/// )));
+/// rewrite.apply(&mut doc);
+///
+/// assert_eq!(
+///     doc.to_markup::<Gemtext>(),
+///     "=> /posts/hello.html\n\
+///      => https://portal.example/gemini/example.org/\n\
+///      => mailto:me@example.org\n"
+/// );
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`apply`]: LinkRewrite::apply
+#[derive(Clone, Debug)]
+pub struct LinkRewrite {
+    extension: Option<String>,
+    gemini: GeminiLinks,
+}
+
+/// How a [`LinkRewrite`] treats `gemini://` links
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum GeminiLinks {
+    /// The link is kept as it is
+    #[default]
+    Keep,
+    /// The link goes through an HTTP proxy, with the host and path appended to the prefix
+    Proxy(Uri),
+    /// The link is kept, and marked with `rel="external"` by the `Html` formatter
+    External,
+}
+
+impl Default for LinkRewrite {
+    fn default() -> Self {
+        LinkRewrite {
+            extension: Some("html".to_string()),
+            gemini: GeminiLinks::default(),
+        }
+    }
+}
+
+impl LinkRewrite {
+    /// Constructs a new `LinkRewrite` with the default policy
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the extension that replaces `.gmi` in relative links, or `None` to keep `.gmi`
+    pub fn extension(mut self, extension: Option<&str>) -> Self {
+        self.extension = extension.map(str::to_string);
+        self
+    }
+
+    /// Sets how `gemini://` links are treated
+    pub fn gemini(mut self, gemini: GeminiLinks) -> Self {
+        self.gemini = gemini;
+        self
+    }
+
+    /// Returns the rewritten URI
+    pub fn uri(&self, uri: &UriRef) -> UriRef {
+        if is_gemini(uri) {
+            match &self.gemini {
+                GeminiLinks::Proxy(prefix) => proxy(prefix, uri),
+                GeminiLinks::Keep | GeminiLinks::External => uri.clone(),
+            }
+        } else if uri.scheme().is_none() {
+            self.relative(uri)
+        } else {
+            uri.clone()
+        }
+    }
+
+    /// Returns whether a URI should be marked as external to the published site
+    pub fn is_external(&self, uri: &UriRef) -> bool {
+        self.gemini == GeminiLinks::External && is_gemini(uri)
+    }
+
+    /// Rewrites the URI of every link in `blocks`
+    pub fn apply<'a, I>(&self, blocks: I)
+    where
+        I: IntoIterator<Item = &'a mut Block>,
+    {
+        for block in blocks {
+            if let Block::Link(link) = block {
+                self.apply_link(link);
+            }
+        }
+    }
+
+    /// Rewrites the URI of a link
+    pub fn apply_link(&self, link: &mut Link) {
+        *link.uri_mut() = self.uri(link.uri());
+    }
+
+    fn relative(&self, uri: &UriRef) -> UriRef {
+        // A relative path reference starts with its path, followed by any query and fragment
+        let (path, rest) = uri.as_str().split_at(uri.path().len());
+        let (extension, stem) = match (&self.extension, path.strip_suffix(".gmi")) {
+            (Some(extension), Some(stem)) => (extension, stem),
+            _ => return uri.clone(),
+        };
+        UriRef::new(format!("{}.{}{}", stem, extension, rest)).unwrap_or_else(|_| uri.clone())
+    }
+}
+
+/// Returns whether a URI is a `gemini://` URI or a network-path reference
+fn is_gemini(uri: &UriRef) -> bool {
+    match uri.scheme() {
+        Some(scheme) => scheme.eq_ignore_ascii_case("gemini"),
+        None => uri.authority().is_some(),
+    }
+}
+
+/// Appends the host and path of a `gemini://` URI to a proxy prefix
+fn proxy(prefix: &Uri, uri: &UriRef) -> UriRef {
+    let prefix = prefix.to_string();
+    let start = uri.scheme().map_or(0, |scheme| scheme.len() + 1);
+    let target = uri.as_str()[start..].trim_start_matches('/');
+    UriRef::new(format!("{}/{}", prefix.trim_end_matches('/'), target))
+        .unwrap_or_else(|_| uri.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use http::uri::Uri;
+
+    fn rewrite(rewrite: &LinkRewrite, uri: &str) -> String {
+        rewrite.uri(&uri.parse().unwrap()).to_string()
+    }
+
+    fn is_external(rewrite: &LinkRewrite, uri: &str) -> bool {
+        rewrite.is_external(&uri.parse().unwrap())
+    }
+
+    #[test]
+    fn relative() {
+        let default = LinkRewrite::new();
+        assert_eq!(rewrite(&default, "foo.gmi"), "foo.html");
+        assert_eq!(rewrite(&default, "/a/b.gmi?q=1"), "/a/b.html?q=1");
+        assert_eq!(rewrite(&default, "posts/foo.gmi"), "posts/foo.html");
+        assert_eq!(rewrite(&default, "../up.gmi#top"), "../up.html#top");
+        assert_eq!(rewrite(&default, "#top"), "#top");
+        assert_eq!(rewrite(&default, "//host/a.gmi"), "//host/a.gmi");
+        assert_eq!(rewrite(&default, "/a.gmi/"), "/a.gmi/");
+        assert_eq!(rewrite(&default, "/cat.png"), "/cat.png");
+        assert_eq!(
+            rewrite(&default, "mailto:me@host.gmi"),
+            "mailto:me@host.gmi"
+        );
+        assert_eq!(
+            rewrite(&default, "https://host/a.gmi"),
+            "https://host/a.gmi"
+        );
+
+        let keep = LinkRewrite::new().extension(None);
+        assert_eq!(rewrite(&keep, "foo.gmi"), "foo.gmi");
+    }
+
+    #[test]
+    fn gemini() {
+        let proxy = LinkRewrite::new().gemini(GeminiLinks::Proxy(Uri::from_static(
+            "https://portal.example",
+        )));
+        assert_eq!(
+            rewrite(&proxy, "gemini://host/a.gmi?q"),
+            "https://portal.example/host/a.gmi?q"
+        );
+        assert_eq!(
+            rewrite(&proxy, "GEMINI://host/"),
+            "https://portal.example/host/"
+        );
+        assert_eq!(
+            rewrite(&proxy, "//host/a.gmi"),
+            "https://portal.example/host/a.gmi"
+        );
+        assert!(!is_external(&proxy, "gemini://host/"));
+
+        let external = LinkRewrite::new().gemini(GeminiLinks::External);
+        assert_eq!(rewrite(&external, "gemini://host/"), "gemini://host/");
+        assert_eq!(rewrite(&external, "//host/a.gmi"), "//host/a.gmi");
+        assert!(is_external(&external, "gemini://host/"));
+        assert!(is_external(&external, "Gemini://host/"));
+        assert!(is_external(&external, "//host/a.gmi"));
+        assert!(!is_external(&external, "/a.gmi"));
+    }
+}
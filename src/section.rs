@@ -0,0 +1,234 @@
+use crate::{Block, Content, Level};
+
+/// A tree of sections over a flat list of [`Block`]s
+///
+/// Each heading starts a section that holds the blocks up to the next heading,
+/// and the sections of any deeper headings that follow it.
+/// Blocks before the first heading are the preamble.
+/// Flattening the tree with [`into_blocks`] gives back the original blocks.
+///
+/// # Example
+/// ```
+/// # fn main() -> mdiu::Result<()> {
+/// use mdiu::{Document, Gemtext, Outline, ToMarkup};
+///
+/// let doc = Document::new()
+///     .h1("Manual")
+///     .h2("Usage")
+///     .text("run it")
+///     .h2("Install")
+///     .text("build it")
+///     .build()?;
+///
+/// let mut outline = Outline::new(&doc);
+/// let manual = &mut outline.sections_mut()[0];
+/// assert_eq!(manual.children()[1].heading().as_ref(), "Install");
+///
+/// // Move the install instructions first
+/// manual.children_mut().swap(0, 1);
+///
+/// assert_eq!(
+///     outline.into_blocks().to_markup::<Gemtext>(),
+///     "# Manual\n## Install\nbuild it\n## Usage\nrun it\n"
+/// );
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`into_blocks`]: Outline::into_blocks
+#[derive(Clone, Debug, Default)]
+pub struct Outline {
+    preamble: Vec<Block>,
+    sections: Vec<Section>,
+}
+
+/// A heading with its body and nested sections, part of an [`Outline`]
+#[derive(Clone, Debug)]
+pub struct Section {
+    level: Level,
+    heading: Content,
+    body: Vec<Block>,
+    children: Vec<Section>,
+}
+
+impl Outline {
+    /// Constructs the section tree of `blocks`
+    pub fn new(blocks: &[Block]) -> Self {
+        let mut outline = Outline::default();
+        // The current section, and the sections it is nested in
+        let mut open: Vec<Section> = Vec::new();
+
+        for block in blocks {
+            match block {
+                Block::Heading(level, heading) => {
+                    while open.last().is_some_and(|section| section.level >= *level) {
+                        close(&mut open, &mut outline.sections);
+                    }
+                    open.push(Section::new(*level, heading.clone()));
+                }
+                block => match open.last_mut() {
+                    Some(section) => section.body.push(block.clone()),
+                    None => outline.preamble.push(block.clone()),
+                },
+            }
+        }
+        while !open.is_empty() {
+            close(&mut open, &mut outline.sections);
+        }
+
+        outline
+    }
+
+    /// Returns the blocks before the first heading
+    pub fn preamble(&self) -> &[Block] {
+        &self.preamble
+    }
+    /// Returns a mutable reference to the blocks before the first heading
+    pub fn preamble_mut(&mut self) -> &mut Vec<Block> {
+        &mut self.preamble
+    }
+
+    /// Returns the top-level sections
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+    /// Returns a mutable reference to the top-level sections
+    pub fn sections_mut(&mut self) -> &mut Vec<Section> {
+        &mut self.sections
+    }
+
+    /// Flattens the tree into blocks, in order
+    pub fn into_blocks(self) -> Vec<Block> {
+        let mut blocks = self.preamble;
+        for section in self.sections {
+            section.flatten(&mut blocks);
+        }
+        blocks
+    }
+}
+
+/// Moves the current section into its parent, or the top level
+fn close(open: &mut Vec<Section>, sections: &mut Vec<Section>) {
+    if let Some(section) = open.pop() {
+        match open.last_mut() {
+            Some(parent) => parent.children.push(section),
+            None => sections.push(section),
+        }
+    }
+}
+
+impl Section {
+    /// Constructs a new `Section` with no body or children
+    pub fn new(level: Level, heading: Content) -> Self {
+        Section {
+            level,
+            heading,
+            body: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Returns the level of the heading
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+    /// Returns a mutable reference to the level of the heading
+    pub fn level_mut(&mut self) -> &mut Level {
+        &mut self.level
+    }
+
+    /// Returns the text of the heading
+    pub fn heading(&self) -> &Content {
+        &self.heading
+    }
+    /// Returns a mutable reference to the text of the heading
+    pub fn heading_mut(&mut self) -> &mut Content {
+        &mut self.heading
+    }
+
+    /// Returns the blocks between the heading and the next heading
+    pub fn body(&self) -> &[Block] {
+        &self.body
+    }
+    /// Returns a mutable reference to the blocks between the heading and the next heading
+    pub fn body_mut(&mut self) -> &mut Vec<Block> {
+        &mut self.body
+    }
+
+    /// Returns the nested sections
+    pub fn children(&self) -> &[Section] {
+        &self.children
+    }
+    /// Returns a mutable reference to the nested sections
+    pub fn children_mut(&mut self) -> &mut Vec<Section> {
+        &mut self.children
+    }
+
+    /// Flattens the section into blocks, starting with its heading
+    pub fn into_blocks(self) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        self.flatten(&mut blocks);
+        blocks
+    }
+
+    fn flatten(self, blocks: &mut Vec<Block>) {
+        blocks.push(Block::Heading(self.level, self.heading));
+        blocks.extend(self.body);
+        for child in self.children {
+            child.flatten(blocks);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn tree() {
+        let doc = Document::new()
+            .text("preamble")
+            .h1("a")
+            .h3("b")
+            .text("b body")
+            .h2("c")
+            .h1("d")
+            .build()
+            .unwrap();
+        let outline = Outline::new(&doc);
+
+        assert_eq!(outline.preamble().len(), 1);
+        let headings: Vec<_> = outline
+            .sections()
+            .iter()
+            .map(|s| s.heading().as_ref())
+            .collect();
+        assert_eq!(headings, ["a", "d"]);
+        let a = &outline.sections()[0];
+        let children: Vec<_> = a.children().iter().map(|s| s.heading().as_ref()).collect();
+        assert_eq!(children, ["b", "c"]);
+        assert_eq!(a.children()[0].body().len(), 1);
+
+        assert_eq!(
+            outline.into_blocks().to_markup::<Gemtext>(),
+            doc.to_markup::<Gemtext>()
+        );
+    }
+
+    #[test]
+    fn extract_chapter() {
+        let doc = Document::new()
+            .h1("one")
+            .h2("one.one")
+            .h1("two")
+            .text("text")
+            .build()
+            .unwrap();
+        let chapter = Outline::new(&doc).sections_mut().remove(1);
+
+        assert_eq!(
+            chapter.into_blocks().to_markup::<Gemtext>(),
+            "# two\ntext\n"
+        );
+    }
+}
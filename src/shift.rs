@@ -0,0 +1,98 @@
+use crate::{Block, Error, Level, Result};
+
+/// What [`shift_headings`] does with headings shifted past [`Level::Three`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// The heading becomes a [`Level::Three`] heading
+    #[default]
+    Clamp,
+    /// The heading becomes a [`Block::Text`] line
+    ///
+    /// Text starting with a line type prefix, such as a heading of `#hashtag`,
+    /// is then rejected by [`Document::strict`](crate::Document::strict).
+    Text,
+    /// No heading is shifted, and [`Error::InvalidLevel`] is returned
+    Error,
+}
+
+/// Demotes every heading in `blocks` by `n` levels, such as when embedding one document in another
+///
+/// # Errors
+/// Returns [`Error::InvalidLevel`] with [`Overflow::Error`] if any heading would pass
+/// [`Level::Three`], leaving the blocks unchanged.
+///
+/// # Example
+/// ```
+/// # fn main() -> mdiu::Result<()> {
+/// use mdiu::{shift_headings, Document, Gemtext, Overflow, ToMarkup};
+///
+/// let mut post = Document::new().h1("post").h2("part").h3("detail").build()?;
+/// shift_headings(&mut post, 1, Overflow::Text)?;
+///
+/// assert_eq!(post.to_markup::<Gemtext>(), "## post\n### part\ndetail\n");
+/// # Ok(())
+/// # }
+/// ```
+pub fn shift_headings(blocks: &mut [Block], n: u8, overflow: Overflow) -> Result<()> {
+    let shift = |level: Level| Level::try_from(u8::from(level).saturating_add(n)).ok();
+
+    if overflow == Overflow::Error {
+        let overflows = blocks
+            .iter()
+            .any(|block| matches!(block, Block::Heading(level, _) if shift(*level).is_none()));
+        if overflows {
+            return Err(Error::InvalidLevel);
+        }
+    }
+
+    for block in blocks.iter_mut() {
+        if let Block::Heading(level, text) = block {
+            *block = match (shift(*level), overflow) {
+                (Some(level), _) => Block::Heading(level, text.clone()),
+                (None, Overflow::Text) => Block::Text(text.clone()),
+                (None, _) => Block::Heading(Level::Three, text.clone()),
+            };
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn doc() -> Vec<Block> {
+        Document::new()
+            .h1("one")
+            .text("text")
+            .h3("three")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn shift() {
+        let mut blocks = doc();
+        shift_headings(&mut blocks, 0, Overflow::Error).unwrap();
+        assert_eq!(blocks.to_markup::<Gemtext>(), "# one\ntext\n### three\n");
+
+        let mut blocks = doc();
+        shift_headings(&mut blocks, 1, Overflow::Clamp).unwrap();
+        assert_eq!(blocks.to_markup::<Gemtext>(), "## one\ntext\n### three\n");
+
+        let mut blocks = doc();
+        shift_headings(&mut blocks, u8::MAX, Overflow::Text).unwrap();
+        assert_eq!(blocks.to_markup::<Gemtext>(), "one\ntext\nthree\n");
+    }
+
+    #[test]
+    fn overflow_error() {
+        let mut blocks = doc();
+        assert_eq!(
+            shift_headings(&mut blocks, 1, Overflow::Error),
+            Err(Error::InvalidLevel)
+        );
+        assert_eq!(blocks.to_markup::<Gemtext>(), "# one\ntext\n### three\n");
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashSet;
+
+/// Converts heading text into a slug for use as an `id` or URI fragment
+///
+/// Letters and digits are lowercased, spaces, `-` and `_` become single hyphens,
+/// and other characters are dropped. Text without letters or digits becomes `section`.
+///
+/// # Example
+/// ```
+/// use mdiu::slug;
+///
+/// assert_eq!(slug("Hello, World!"), "hello-world");
+/// assert_eq!(slug("C++ & Rust_2021"), "c-rust-2021");
+/// assert_eq!(slug("Ünïcode"), "ünïcode");
+/// ```
+pub fn slug(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    let mut separate = false;
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            if separate && !slug.is_empty() {
+                slug.push('-');
+            }
+            separate = false;
+            slug.extend(c.to_lowercase());
+        } else if c.is_whitespace() || c == '-' || c == '_' {
+            separate = true;
+        }
+    }
+    if slug.is_empty() {
+        slug.push_str("section");
+    }
+    slug
+}
+
+/// Generates unique slugs for the headings of a document
+///
+/// Repeated slugs get a numbered suffix, such as `notes-1` and `notes-2`,
+/// so that every heading of a document can be linked to.
+///
+/// # Example
+/// ```
+/// use mdiu::Slugs;
+///
+/// let mut slugs = Slugs::new();
+///
+/// assert_eq!(slugs.unique("Notes"), "notes");
+/// assert_eq!(slugs.unique("notes"), "notes-1");
+/// assert_eq!(slugs.unique("Notes!"), "notes-2");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Slugs {
+    used: HashSet<String>,
+}
+
+impl Slugs {
+    /// Constructs a new `Slugs` with no slugs used
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the slug of `text`, with a suffix if it has already been used
+    pub fn unique(&mut self, text: &str) -> String {
+        let slug = slug(text);
+        let mut unique = slug.clone();
+        let mut n = 0;
+        while self.used.contains(&unique) {
+            n += 1;
+            unique = format!("{}-{}", slug, n);
+        }
+        self.used.insert(unique.clone());
+        unique
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn slugs() {
+        assert_eq!(slug("  --Leading and trailing--  "), "leading-and-trailing");
+        assert_eq!(slug("!!!"), "section");
+
+        let mut slugs = Slugs::new();
+        assert_eq!(slugs.unique("a 1"), "a-1");
+        assert_eq!(slugs.unique("a"), "a");
+        // `a-1` is taken by the first heading
+        assert_eq!(slugs.unique("a"), "a-2");
+        assert_eq!(slugs.unique("!"), "section");
+    }
+}
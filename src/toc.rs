@@ -0,0 +1,233 @@
+use crate::{Block, Content, Level, Slugs};
+
+/// A table of contents built from the headings of a document
+///
+/// Each entry has the slug that `Html` gives its heading with `HeadingStyle::Ids`,
+/// so the table can link to headings of the same document.
+///
+/// # Example
+/// ```
+/// # fn main() -> mdiu::Result<()> {
+/// use mdiu::{Document, Gemtext, Level, Toc, ToMarkup};
+///
+/// let doc = Document::new()
+///     .h1("Manual")
+///     .h2("Install")
+///     .text("cargo install")
+///     .h2("Usage")
+///     .build()?;
+/// let toc = Toc::new(&doc);
+///
+/// let entry = &toc.entries()[1];
+/// assert!(matches!(entry.level(), Level::Two));
+/// assert_eq!(entry.text().as_ref(), "Install");
+/// assert_eq!(entry.index(), 1);
+/// assert_eq!(entry.slug(), "install");
+///
+/// assert_eq!(
+///     toc.list_items().to_markup::<Gemtext>(),
+///     "* Manual\n* Install\n* Usage\n"
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Toc {
+    entries: Vec<TocEntry>,
+}
+
+/// A heading in a [`Toc`]
+#[derive(Clone, Debug)]
+pub struct TocEntry {
+    level: Level,
+    text: Content,
+    index: usize,
+    slug: String,
+}
+
+impl TocEntry {
+    /// Returns the level of the heading
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+
+    /// Returns the text of the heading
+    pub fn text(&self) -> &Content {
+        &self.text
+    }
+
+    /// Returns the index of the heading in the blocks
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the unique slug of the heading
+    pub fn slug(&self) -> &str {
+        &self.slug
+    }
+}
+
+impl Toc {
+    /// Constructs a table of contents from the headings in `blocks`
+    pub fn new<'a, I>(blocks: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Block>,
+    {
+        let mut slugs = Slugs::new();
+        let entries = blocks
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, block)| match block {
+                Block::Heading(level, text) => Some(TocEntry {
+                    level: *level,
+                    text: text.clone(),
+                    index,
+                    slug: slugs.unique(text.as_ref()),
+                }),
+                _ => None,
+            })
+            .collect();
+        Toc { entries }
+    }
+
+    /// Returns the entries in document order
+    pub fn entries(&self) -> &[TocEntry] {
+        &self.entries
+    }
+
+    /// Returns whether there are no headings
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns a list item for each entry, since Gemtext cannot link to a heading
+    pub fn list_items(&self) -> Vec<Block> {
+        self.entries
+            .iter()
+            .map(|entry| Block::ListItem(entry.text.clone()))
+            .collect()
+    }
+
+    /// Returns nested HTML lists of links to the headings, available with the `html` feature
+    ///
+    /// Lists are nested by heading level, starting from the level of the first heading.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> mdiu::Result<()> {
+    /// use mdiu::{Document, Toc};
+    ///
+    /// let doc = Document::new().h2("Install").h3("Linux").h2("Usage").build()?;
+    ///
+    /// assert_eq!(
+    ///     Toc::new(&doc).html(),
+    ///     r##"<ul>
+    /// <li><a href="#install">Install</a>
+    /// <ul>
+    /// <li><a href="#linux">Linux</a></li>
+    /// </ul>
+    /// </li>
+    /// <li><a href="#usage">Usage</a></li>
+    /// </ul>
+    /// "##
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "html")]
+    pub fn html(&self) -> String {
+        use crate::html::escape;
+        use std::fmt::Write;
+
+        let mut s = String::new();
+        // The heading level of each open list, each but the last inside an open list item
+        let mut open: Vec<Level> = Vec::new();
+        for entry in &self.entries {
+            let level = entry.level;
+            match open.last() {
+                None => {
+                    s.push_str("<ul>\n");
+                    open.push(level);
+                }
+                Some(&last) if level > last => {
+                    s.push_str("\n<ul>\n");
+                    open.push(level);
+                }
+                Some(_) => {
+                    s.push_str("</li>\n");
+                    // A skipped level stays in the list it is nested in
+                    while open.len() > 1 && level <= open[open.len() - 2] {
+                        s.push_str("</ul>\n</li>\n");
+                        open.pop();
+                    }
+                }
+            }
+            write!(
+                s,
+                "<li><a href=\"#{}\">{}</a>",
+                escape(&entry.slug),
+                escape(&entry.text)
+            )
+            .expect("writing to a String cannot fail");
+        }
+        if !open.is_empty() {
+            s.push_str("</li>\n");
+            for _ in 1..open.len() {
+                s.push_str("</ul>\n</li>\n");
+            }
+            s.push_str("</ul>\n");
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn entries() {
+        let doc = Document::new()
+            .text("intro")
+            .h3("Notes")
+            .h1("Notes")
+            .build()
+            .unwrap();
+        let toc = Toc::new(&doc);
+
+        let entries: Vec<_> = toc
+            .entries()
+            .iter()
+            .map(|e| (e.index(), e.slug()))
+            .collect();
+        assert_eq!(entries, [(1, "notes"), (2, "notes-1")]);
+        assert!(Toc::new(&Vec::new()).is_empty());
+    }
+
+    #[cfg(feature = "html")]
+    #[test]
+    fn html() {
+        let doc = Document::new()
+            .h1("a")
+            .h3("b")
+            .h3("c")
+            .h2("d")
+            .h1("<e>")
+            .build()
+            .unwrap();
+
+        let expected = r##"<ul>
+<li><a href="#a">a</a>
+<ul>
+<li><a href="#b">b</a></li>
+<li><a href="#c">c</a></li>
+<li><a href="#d">d</a></li>
+</ul>
+</li>
+<li><a href="#e">&lt;e&gt;</a></li>
+</ul>
+"##;
+        assert_eq!(Toc::new(&doc).html(), expected);
+        assert_eq!(Toc::default().html(), "");
+    }
+}
@@ -12,7 +12,7 @@ fn kitchen_sink() -> Result<Vec<Block>> {
         .quote("quote")
         .preformatted("@_@".to_string())
         .text("more text")
-        .preformatted_with_alt("@_@".into(), "emoticon")
+        .preformatted_with_alt("@_@", "emoticon")
         .list_item("one item")
         .link(Uri::from_static("no-text"))
         .link_with_label(Uri::from_static("with-text"), "with text")
@@ -49,6 +49,20 @@ more text
     assert_eq!(expected, &kitchen_sink().unwrap().to_markup::<Gemtext>());
 }
 
+#[test]
+fn write_markup() {
+    let mut bytes = Vec::new();
+    kitchen_sink()
+        .unwrap()
+        .write_markup(&Gemtext, &mut bytes)
+        .unwrap();
+
+    assert_eq!(
+        String::from_utf8(bytes).unwrap(),
+        kitchen_sink().unwrap().to_markup::<Gemtext>()
+    );
+}
+
 #[cfg(feature = "html")]
 #[test]
 fn html() {
@@ -64,7 +78,7 @@ fn html() {
 @_@
 </pre>
 <p>more text</p>
-<pre>
+<pre aria-label="emoticon">
 @_@
 </pre>
 <ul>
@@ -115,3 +129,39 @@ more text
 
     assert_eq!(expected, &kitchen_sink().unwrap().to_markup::<Markdown>());
 }
+
+#[cfg(feature = "parsing")]
+#[test]
+fn parsing() {
+    let gemtext = kitchen_sink().unwrap().to_markup::<Gemtext>();
+    let parsed = parse(&gemtext).unwrap();
+
+    assert_eq!(gemtext, parsed.to_markup::<Gemtext>());
+}
+
+#[test]
+fn custom_containers() {
+    use std::collections::VecDeque;
+
+    struct Page {
+        blocks: VecDeque<Block>,
+    }
+
+    impl<'a> IntoIterator for &'a Page {
+        type Item = &'a Block;
+        type IntoIter = std::collections::vec_deque::Iter<'a, Block>;
+
+        fn into_iter(self) -> Self::IntoIter {
+            self.blocks.iter()
+        }
+    }
+
+    let page = Page {
+        blocks: kitchen_sink().unwrap().into(),
+    };
+
+    assert_eq!(
+        page.to_markup::<Gemtext>(),
+        kitchen_sink().unwrap().to_markup::<Gemtext>()
+    );
+}